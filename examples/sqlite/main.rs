@@ -12,15 +12,13 @@ async fn main() {
     let mut migrator = Migrator::default();
     migrator.add_migrations(migrations::migrations()).unwrap();
     // There are two way to run migration. Either you can create cli as shown below
-    let mut conn = pool.acquire().await.unwrap();
-    MigrationCommand::parse_and_run(&mut *conn, Box::new(migrator))
+    MigrationCommand::parse_and_run_with_pool(&pool, Box::new(migrator))
         .await
         .unwrap();
     // Or you can directly use migrator run function instead of creating
     // cli
     // migrator
-    //     .run(&mut *conn, sqlx_migrator::migrator::Plan::apply_all())
+    //     .run_with_pool(&pool, &sqlx_migrator::migrator::Plan::apply_all())
     //     .await
     //     .unwrap();
-    conn.close().await.unwrap();
 }
@@ -0,0 +1,171 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{DeriveInput, Expr, LitBool, Token, Type};
+
+/// Parse comma separated list of expression present inside parenthesis
+fn parse_list(meta: &syn::meta::ParseNestedMeta<'_>, list: &mut Vec<Expr>) -> syn::Result<()> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    list.extend(Punctuated::<Expr, Token![,]>::parse_terminated(&content)?);
+    Ok(())
+}
+
+/// Options provided using `#[migration(...)]` attribute
+#[derive(Default)]
+struct MigrationAttributes {
+    db: Option<Type>,
+    app: Option<Expr>,
+    name: Option<Expr>,
+    parents: Vec<Expr>,
+    operations: Vec<Expr>,
+    replaces: Vec<Expr>,
+    run_before: Vec<Expr>,
+    atomic: Option<LitBool>,
+    is_virtual: Option<LitBool>,
+}
+
+impl MigrationAttributes {
+    /// Parse all `#[migration(...)]` attributes of derive input
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("migration"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("db") {
+                    attributes.db = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("app") {
+                    attributes.app = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("name") {
+                    attributes.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("parents") {
+                    parse_list(&meta, &mut attributes.parents)?;
+                } else if meta.path.is_ident("operations") {
+                    parse_list(&meta, &mut attributes.operations)?;
+                } else if meta.path.is_ident("replaces") {
+                    parse_list(&meta, &mut attributes.replaces)?;
+                } else if meta.path.is_ident("run_before") {
+                    parse_list(&meta, &mut attributes.run_before)?;
+                } else if meta.path.is_ident("atomic") {
+                    attributes.atomic = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("virtual") {
+                    attributes.is_virtual = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unsupported migration attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(attributes)
+    }
+}
+
+/// Expand derive input to implementation of `Migration` trait
+pub(crate) fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let MigrationAttributes {
+        db,
+        app,
+        name,
+        parents,
+        operations,
+        replaces,
+        run_before,
+        atomic,
+        is_virtual,
+    } = MigrationAttributes::parse(input)?;
+
+    let db = db.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "missing database type, add #[migration(db = ...)]",
+        )
+    })?;
+    let app = app.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "missing app name, add #[migration(app = ...)]",
+        )
+    })?;
+    // use name of module where migration is defined as default name
+    let name = name.map_or_else(
+        || {
+            quote! {
+                ::std::option::Option::unwrap_or_default(
+                    ::std::iter::Iterator::next(&mut ::std::module_path!().rsplit("::"))
+                )
+            }
+        },
+        |name| quote! { #name },
+    );
+    let replaces = (!replaces.is_empty()).then(|| {
+        quote! {
+            fn replaces(&self) -> ::std::vec::Vec<
+                ::std::boxed::Box<dyn ::sqlx_migrator::migration::Migration<#db>>
+            > {
+                ::std::vec![#(::std::boxed::Box::new(#replaces)),*]
+            }
+        }
+    });
+    let run_before = (!run_before.is_empty()).then(|| {
+        quote! {
+            fn run_before(&self) -> ::std::vec::Vec<
+                ::std::boxed::Box<dyn ::sqlx_migrator::migration::Migration<#db>>
+            > {
+                ::std::vec![#(::std::boxed::Box::new(#run_before)),*]
+            }
+        }
+    });
+    let atomic = atomic.map(|atomic| {
+        quote! {
+            fn is_atomic(&self) -> bool {
+                #atomic
+            }
+        }
+    });
+    let is_virtual = is_virtual.map(|is_virtual| {
+        quote! {
+            fn is_virtual(&self) -> bool {
+                #is_virtual
+            }
+        }
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sqlx_migrator::migration::Migration<#db>
+            for #ident #ty_generics #where_clause
+        {
+            fn app(&self) -> &str {
+                #app
+            }
+
+            fn name(&self) -> &str {
+                #name
+            }
+
+            fn parents(&self) -> ::std::vec::Vec<
+                ::std::boxed::Box<dyn ::sqlx_migrator::migration::Migration<#db>>
+            > {
+                ::std::vec![#(::std::boxed::Box::new(#parents)),*]
+            }
+
+            fn operations(&self) -> ::std::vec::Vec<
+                ::std::boxed::Box<dyn ::sqlx_migrator::operation::Operation<#db>>
+            > {
+                ::std::vec![#(::std::boxed::Box::new(#operations)),*]
+            }
+
+            #replaces
+
+            #run_before
+
+            #atomic
+
+            #is_virtual
+        }
+    })
+}
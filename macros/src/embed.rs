@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+/// Return sorted list of sub directories of provided directory
+fn sorted_sub_directories(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut directories = vec![];
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        if entry_path.is_dir() {
+            directories.push(entry_path);
+        }
+    }
+    directories.sort();
+    Ok(directories)
+}
+
+/// Get name of directory as utf-8 string
+fn directory_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|name| name.to_str())
+}
+
+/// Prefix used by comment directive of sql migration
+const DIRECTIVE_PREFIX: &str = "migrator:";
+
+/// Metadata of sql migration parsed from comment directives of up sql
+struct Directives {
+    parents: Vec<(String, String)>,
+    atomic: bool,
+    destructible: bool,
+}
+
+/// Parse comment directives present at the start of up sql. Directives are
+/// parsed same as `sqlx_migrator::sql::SqlMigration::new`, tests of
+/// `sqlx_migrator` compare embedded and loaded migrations of same directory to
+/// keep both parsers in sync
+fn parse_directives(up: &str) -> Result<Directives, String> {
+    let mut directives = Directives {
+        parents: vec![],
+        atomic: true,
+        destructible: false,
+    };
+    for line in up.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        // directives are only read from leading comment lines
+        let Some(comment) = line.strip_prefix("--") else {
+            break;
+        };
+        let Some(directive) = comment.trim().strip_prefix(DIRECTIVE_PREFIX) else {
+            continue;
+        };
+        let directive = directive.trim();
+        let (key, value) = directive
+            .split_once(char::is_whitespace)
+            .map_or((directive, ""), |(key, value)| (key, value.trim()));
+        match (key, value) {
+            ("parent", parent) => {
+                let Some((parent_app, parent_name)) = parent.split_once('/') else {
+                    return Err(format!("parent {parent} is not in format <app>/<name>"));
+                };
+                directives
+                    .parents
+                    .push((parent_app.to_string(), parent_name.to_string()));
+            }
+            ("no-transaction", "") => directives.atomic = false,
+            ("destructible", "") => directives.destructible = true,
+            _ => return Err(format!("unknown directive {directive}")),
+        }
+    }
+    Ok(directives)
+}
+
+/// Expand sql migrations directory to list of sql migrations whose sql is
+/// embedded using `include_str!`. Comment directives are parsed at compile
+/// time so invalid directive fails compilation
+pub(crate) fn expand(path: &LitStr) -> syn::Result<TokenStream> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| syn::Error::new(path.span(), "CARGO_MANIFEST_DIR is not set"))?;
+    let directory = Path::new(&manifest_dir).join(path.value());
+    let io_error = |error: std::io::Error| {
+        syn::Error::new(
+            path.span(),
+            format!("failed to read {}: {error}", directory.display()),
+        )
+    };
+    let invalid_path = |invalid: &Path| {
+        syn::Error::new(
+            path.span(),
+            format!("{} is not valid utf-8 path", invalid.display()),
+        )
+    };
+    let mut migrations = vec![];
+    for app_path in sorted_sub_directories(&directory).map_err(io_error)? {
+        let app = directory_name(&app_path).ok_or_else(|| invalid_path(&app_path))?;
+        for migration_path in sorted_sub_directories(&app_path).map_err(io_error)? {
+            let up_path = migration_path.join("up.sql");
+            if !up_path.is_file() {
+                continue;
+            }
+            let name =
+                directory_name(&migration_path).ok_or_else(|| invalid_path(&migration_path))?;
+            let up = up_path.to_str().ok_or_else(|| invalid_path(&up_path))?;
+            let directives = std::fs::read_to_string(&up_path)
+                .map_err(|error| {
+                    syn::Error::new(
+                        path.span(),
+                        format!("failed to read {}: {error}", up_path.display()),
+                    )
+                })
+                .and_then(|up_sql| {
+                    parse_directives(&up_sql).map_err(|message| {
+                        syn::Error::new(
+                            path.span(),
+                            format!("invalid sql migration {app}/{name}: {message}"),
+                        )
+                    })
+                })?;
+            let down_path = migration_path.join("down.sql");
+            let down = if down_path.is_file() {
+                let down = down_path.to_str().ok_or_else(|| invalid_path(&down_path))?;
+                quote! {
+                    ::std::option::Option::Some(
+                        ::std::string::ToString::to_string(include_str!(#down))
+                    )
+                }
+            } else {
+                quote! { ::std::option::Option::None }
+            };
+            let destructible = directives.destructible;
+            let atomic = directives.atomic;
+            let parents = directives
+                .parents
+                .iter()
+                .map(|(parent_app, parent_name)| quote! { .add_parent(#parent_app, #parent_name) });
+            migrations.push(quote! {
+                ::sqlx_migrator::sql::SqlMigration::from_operation(
+                    #app,
+                    #name,
+                    ::sqlx_migrator::sql::SqlFileOperation::new(include_str!(#up), #down)
+                        .set_destructible(#destructible),
+                )
+                .set_atomic(#atomic)
+                #(#parents)*
+            });
+        }
+    }
+    Ok(quote! {
+        ::sqlx_migrator::sql::link_migrations(::std::vec![#(#migrations),*])
+    })
+}
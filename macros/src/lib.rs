@@ -0,0 +1,59 @@
+//! Procedural macros for `sqlx_migrator`
+//!
+//! This crate is not intended to be used directly. Enable `macros` feature of
+//! `sqlx_migrator` crate and use macros re-exported by it.
+
+use proc_macro::TokenStream;
+
+/// Module for deriving migration trait
+mod derive;
+/// Module for embedding sql migrations directory
+mod embed;
+
+/// Embed sql migrations directory at compile time
+///
+/// Path is relative to directory containing `Cargo.toml` of crate and
+/// directory layout is same as of `sqlx_migrator::sql::load_migrations`.
+/// Macro expands to `Vec<Box<dyn Migration<DB>>>`. Comment directives of
+/// `up.sql` are parsed at compile time and invalid directive results in
+/// compile error.
+///
+/// Sql files are embedded using `include_str!` so changes to existing files
+/// trigger rebuild. Cargo cannot track directory from procedural macro so
+/// newly added migration directory or `down.sql` file is only picked up after
+/// rebuild. Add `build.rs` to crate so it is rebuilt when directory changes
+///
+/// ```rust,ignore
+/// fn main() {
+///     println!("cargo:rerun-if-changed=migrations");
+/// }
+/// ```
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let path = syn::parse_macro_input!(input as syn::LitStr);
+    embed::expand(&path)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `Migration` trait for struct
+///
+/// Migration is configured using `#[migration(...)]` attribute which supports
+/// following options
+/// - `db = Type`: database for which migration is implemented. Required
+/// - `app = "app"`: app name of migration. Required
+/// - `name = "name"`: name of migration. Defaults to name of module where
+///   struct is defined
+/// - `parents(A, B)`: list of parent migrations
+/// - `operations(A, B)`: list of operations
+/// - `replaces(A, B)`: list of migrations which are replaced by migration
+/// - `run_before(A, B)`: list of migrations which should run after migration
+/// - `atomic = false`: whether migration is atomic or not
+/// - `virtual = true`: whether migration is virtual or not
+#[proc_macro_derive(Migration, attributes(migration))]
+pub fn derive_migration(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -21,18 +21,39 @@
 //!     Migrator(sqlx_migrator::cli::MigrationCommand),
 //! }
 //! ```
+//!
+//! Output of command can be printed as json, csv or yaml document instead of
+//! text table using `--format` option so that it can be consumed by other
+//! tools
 #![expect(clippy::print_stdout, reason = "allow printing to stdout in cli")]
 use std::io::Write as _;
+use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
-use clap::{Parser, Subcommand};
-use sqlx::Database;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use sqlx::pool::PoolConnection;
+use sqlx::{Database, Pool};
 
 use crate::error::Error;
-use crate::migrator::{Migrate, Plan};
+use crate::migration::{AppliedMigrationSqlRow, HistoryEvent, Migration};
+use crate::migrator::{
+    BorrowedPoolConnection, BoxMigration, DryRunMigration, LockHolder, Migrate, Plan, RunReport,
+    run_plan_with_lock,
+};
+use crate::sync::{OldMigrator, SyncReport};
+
+/// Module for testing
+#[cfg(feature = "sqlite")]
+#[cfg(test)]
+mod tests;
 
 /// Migration command for performing rust based sqlx migrations
 #[derive(Parser, Debug)]
 pub struct MigrationCommand {
+    /// Format in which output of command is printed
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
     #[command(subcommand)]
     sub_command: SubCommand,
 }
@@ -53,6 +74,41 @@ impl MigrationCommand {
         migration_command.run(connection, migrator).await
     }
 
+    /// Parse [`MigrationCommand`] and run migration command line interface
+    /// on connection acquired from pool
+    ///
+    /// # Errors
+    /// If migration command fails to complete and raise some issue
+    pub async fn parse_and_run_with_pool<DB>(
+        pool: &Pool<DB>,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let migration_command = Self::parse();
+        migration_command.run_with_pool(pool, migrator).await
+    }
+
+    /// Parse [`MigrationCommand`] and run migration command line interface
+    /// with old migrator which is used by sync subcommand
+    ///
+    /// # Errors
+    /// If migration command fails to complete and raise some issue
+    pub async fn parse_and_run_with_old_migrator<DB>(
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        old_migrator: &dyn OldMigrator<DB>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let migration_command = Self::parse();
+        migration_command
+            .run_with_old_migrator(connection, migrator, old_migrator)
+            .await
+    }
+
     /// Run migration command line interface
     ///
     /// # Errors
@@ -66,40 +122,446 @@ impl MigrationCommand {
         DB: Database,
     {
         self.sub_command
-            .handle_subcommand(migrator, connection)
+            .handle_subcommand(
+                migrator,
+                &mut CommandConnection::Borrowed(connection),
+                None,
+                self.format,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Run migration command line interface on connection acquired from pool
+    ///
+    /// Single connection is used for whole command so that migration lock is
+    /// acquired and released on same session. Plan of apply and revert is run
+    /// through same lock guard as [`Migrate::run_with_pool`] so connection is
+    /// closed instead of being returned to pool if command is cancelled or
+    /// lock fails to be released. If command fails connection is also closed
+    /// since lock may still be held by its session
+    ///
+    /// # Errors
+    /// If failed to acquire connection or migration command fails to complete
+    /// and raise some issue
+    pub async fn run_with_pool<DB>(
+        &self,
+        pool: &Pool<DB>,
+        migrator: Box<dyn Migrate<DB>>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let mut connection = pool.acquire().await?;
+        let result = self
+            .sub_command
+            .handle_subcommand(
+                migrator,
+                &mut CommandConnection::Pool(&mut connection),
+                None,
+                self.format,
+            )
+            .await;
+        if result.is_err() {
+            connection.close_on_drop();
+        }
+        result
+    }
+
+    /// Run migration command line interface with old migrator which is used
+    /// by sync subcommand
+    ///
+    /// # Errors
+    /// If migration command fails to complete and raise some issue
+    pub async fn run_with_old_migrator<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        old_migrator: &dyn OldMigrator<DB>,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        self.sub_command
+            .handle_subcommand(
+                migrator,
+                &mut CommandConnection::Borrowed(connection),
+                Some(old_migrator),
+                self.format,
+            )
             .await?;
         Ok(())
     }
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    /// Human readable text table
+    Text,
+    /// Json array of records
+    Json,
+    /// Csv document with header row
+    Csv,
+    /// Yaml sequence of records
+    Yaml,
+}
+
+/// Status of migration shown by list command
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum MigrationStatus {
+    Applied,
+    PartiallyApplied,
+    Pending,
+    Replaced,
+}
+
+impl MigrationStatus {
+    /// Symbol used for status in text table
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Applied => "\u{2713}",
+            Self::PartiallyApplied => "\u{25d1}",
+            Self::Pending => "\u{2717}",
+            Self::Replaced => "\u{2194}",
+        }
+    }
+}
+
+/// Status of migration in report of plan which continues on error
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum RunStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl RunStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// Record which can be printed as json, csv or yaml document
+trait Record: Serialize {
+    /// Name of fields in order of csv columns
+    const FIELDS: &'static [&'static str];
+}
+
+#[derive(Serialize)]
+struct MigrationRecord<'a> {
+    id: Option<i32>,
+    app: &'a str,
+    name: &'a str,
+    status: MigrationStatus,
+    applied_time: Option<&'a str>,
+    duration_ms: Option<u128>,
+    applied_by: Option<&'a str>,
+    applied_operations: Option<usize>,
+    total_operations: usize,
+}
+
+impl Record for MigrationRecord<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "app",
+        "name",
+        "status",
+        "applied_time",
+        "duration_ms",
+        "applied_by",
+        "applied_operations",
+        "total_operations",
+    ];
+}
+
+#[derive(Serialize)]
+struct HistoryRecord<'a> {
+    id: i32,
+    app: &'a str,
+    name: &'a str,
+    event: &'a str,
+    event_time: &'a str,
+    performed_by: Option<&'a str>,
+}
+
+impl Record for HistoryRecord<'_> {
+    const FIELDS: &'static [&'static str] =
+        &["id", "app", "name", "event", "event_time", "performed_by"];
+}
+
+#[derive(Serialize)]
+struct PlanRecord<'a> {
+    app: &'a str,
+    name: &'a str,
+}
+
+impl Record for PlanRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["app", "name"];
+}
+
+#[derive(Serialize)]
+struct DryRunRecord<'a> {
+    app: &'a str,
+    name: &'a str,
+    executed: bool,
+    statements: &'a [Option<String>],
+}
+
+impl Record for DryRunRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["app", "name", "executed", "statements"];
+}
+
+#[derive(Serialize)]
+struct EventRecord<'a> {
+    app: &'a str,
+    name: &'a str,
+    event: &'static str,
+}
+
+impl Record for EventRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["app", "name", "event"];
+}
+
+#[derive(Serialize)]
+struct SyncRecord<'a> {
+    app: &'a str,
+    name: &'a str,
+    status: &'static str,
+}
+
+impl Record for SyncRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["app", "name", "status"];
+}
+
+#[derive(Serialize)]
+struct RunRecord<'a> {
+    app: &'a str,
+    name: &'a str,
+    status: RunStatus,
+}
+
+impl Record for RunRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["app", "name", "status"];
+}
+
+#[derive(Serialize)]
+struct LockRecord<'a> {
+    held: bool,
+    session: Option<&'a str>,
+    since: Option<&'a str>,
+}
+
+impl Record for LockRecord<'_> {
+    const FIELDS: &'static [&'static str] = &["held", "session", "since"];
+}
+
+#[derive(Serialize)]
+struct StatusRecord {
+    status: &'static str,
+}
+
+impl Record for StatusRecord {
+    const FIELDS: &'static [&'static str] = &["status"];
+}
+
+/// Print records as json, csv or yaml document. Text format is handled by each
+/// command itself so nothing is printed for text format
+fn print_records<T>(format: Format, records: &[T]) -> Result<(), Error>
+where
+    T: Record,
+{
+    let mut writer = std::io::stdout().lock();
+    write_records(&mut writer, format, records)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write records as json, csv or yaml document to writer
+fn write_records<T>(
+    writer: &mut dyn std::io::Write,
+    format: Format,
+    records: &[T],
+) -> Result<(), Error>
+where
+    T: Record,
+{
+    match format {
+        Format::Text => {}
+        Format::Json => writeln!(writer, "{}", serde_json::to_string_pretty(records)?)?,
+        Format::Yaml => write!(writer, "{}", serde_yaml_ng::to_string(records)?)?,
+        Format::Csv => {
+            writeln!(writer, "{}", T::FIELDS.join(","))?;
+            for record in records {
+                let value = serde_json::to_value(record)?;
+                let row = T::FIELDS
+                    .iter()
+                    .map(|field| {
+                        match &value[field] {
+                            serde_json::Value::Null => String::new(),
+                            serde_json::Value::String(string) => csv_escape(string),
+                            other => csv_escape(&other.to_string()),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                writeln!(writer, "{}", row.join(","))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Quote csv field if it contains comma, quote or new line
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Ask question for confirmation of migrations. Question is printed to stderr
+/// when output is not text so that printed document remains valid
+fn confirm<'a, DB>(
+    format: Format,
+    question: &str,
+    migrations: impl Iterator<Item = &'a &'a Box<dyn Migration<DB>>>,
+) -> Result<bool, Error>
+where
+    DB: Database,
+{
+    let mut writer: Box<dyn std::io::Write> = if format == Format::Text {
+        Box::new(std::io::stdout())
+    } else {
+        Box::new(std::io::stderr())
+    };
+    writeln!(writer, "{question} (y/N)")?;
+    for (position, migration) in migrations.enumerate() {
+        writeln!(
+            writer,
+            "{position}. {} : {}",
+            migration.app(),
+            migration.name()
+        )?;
+    }
+    writer.flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let input_trimmed = input.trim().to_ascii_lowercase();
+    // Only y or yes is considered as confirmation
+    Ok(["y", "yes"].contains(&input_trimmed.as_str()))
+}
+
 #[derive(Subcommand, Debug)]
 enum SubCommand {
     /// Apply migrations
     Apply(Apply),
     /// Drop migration information table. Needs all migrations to be
-    /// reverted else raises error
+    /// reverted else raises error. History table is not dropped
     Drop,
+    /// List history of apply, revert, fake apply, fake revert and sync events
+    /// of migrations
+    History(History),
     /// List migrations along with their status and time applied if migrations
     /// is already applied
     List,
     /// Revert migrations
     Revert(Revert),
+    /// Sync migrations applied by old migrator to migration table
+    Sync(SyncMigrations),
+    /// Show or forcibly release migration lock
+    Lock(LockCommand),
+}
+
+/// Connection on which subcommand is run
+enum CommandConnection<'connection, DB>
+where
+    DB: Database,
+{
+    /// Connection borrowed from caller
+    Borrowed(&'connection mut <DB as Database>::Connection),
+    /// Connection acquired from pool by cli
+    Pool(&'connection mut PoolConnection<DB>),
+}
+
+impl<DB> CommandConnection<'_, DB>
+where
+    DB: Database,
+{
+    /// Run plan with report. Plan on pool connection is run through lock guard
+    /// which closes connection if plan is cancelled or unlock fails
+    async fn run_with_report(
+        &mut self,
+        migrator: &dyn Migrate<DB>,
+        plan: &Plan,
+    ) -> Result<RunReport, Error> {
+        match self {
+            CommandConnection::Borrowed(connection) => {
+                migrator.run_with_report(connection, plan).await
+            }
+            CommandConnection::Pool(connection) => {
+                run_plan_with_lock(migrator, BorrowedPoolConnection(connection), plan).await
+            }
+        }
+    }
+}
+
+impl<DB> Deref for CommandConnection<'_, DB>
+where
+    DB: Database,
+{
+    type Target = <DB as Database>::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            CommandConnection::Borrowed(connection) => connection,
+            CommandConnection::Pool(connection) => connection,
+        }
+    }
+}
+
+impl<DB> DerefMut for CommandConnection<'_, DB>
+where
+    DB: Database,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            CommandConnection::Borrowed(connection) => connection,
+            CommandConnection::Pool(connection) => connection,
+        }
+    }
 }
 
 impl SubCommand {
     async fn handle_subcommand<DB>(
         &self,
         migrator: Box<dyn Migrate<DB>>,
-        connection: &mut <DB as Database>::Connection,
+        connection: &mut CommandConnection<'_, DB>,
+        old_migrator: Option<&dyn OldMigrator<DB>>,
+        format: Format,
     ) -> Result<(), Error>
     where
         DB: Database,
     {
         match self {
-            SubCommand::Apply(apply) => apply.run(connection, migrator).await?,
-            SubCommand::Drop => drop_migrations(connection, migrator).await?,
-            SubCommand::List => list_migrations(connection, migrator).await?,
-            SubCommand::Revert(revert) => revert.run(connection, migrator).await?,
+            SubCommand::Apply(apply) => apply.run(connection, migrator, format).await?,
+            SubCommand::Drop => drop_migrations(&mut **connection, migrator, format).await?,
+            SubCommand::History(history) => {
+                history.run(&mut **connection, migrator, format).await?;
+            }
+            SubCommand::List => list_migrations(&mut **connection, migrator, format).await?,
+            SubCommand::Revert(revert) => revert.run(connection, migrator, format).await?,
+            SubCommand::Sync(sync) => {
+                let old_migrator = old_migrator.ok_or(Error::OldMigratorNotProvided)?;
+                sync.run(&mut **connection, migrator, old_migrator, format)
+                    .await?;
+            }
+            SubCommand::Lock(lock) => lock.run(&mut **connection, migrator, format).await?,
         }
         Ok(())
     }
@@ -108,6 +570,7 @@ impl SubCommand {
 async fn drop_migrations<DB>(
     connection: &mut <DB as Database>::Connection,
     migrator: Box<dyn Migrate<DB>>,
+    format: Format,
 ) -> Result<(), Error>
 where
     DB: Database,
@@ -121,25 +584,92 @@ where
         return Err(Error::AppliedMigrationExists);
     }
     migrator.drop_migration_table_if_exists(connection).await?;
-    println!("Dropped migrations table");
+    if format == Format::Text {
+        println!("Dropped migrations table");
+    }
+    print_records(format, &[StatusRecord { status: "dropped" }])?;
     Ok(())
 }
 
+/// Create record of migrations of plan along with their status
+fn migration_records<'a, DB>(
+    migration_plan: &[&'a BoxMigration<DB>],
+    apply_plan: &[&BoxMigration<DB>],
+    applied_migrations: &'a [AppliedMigrationSqlRow],
+) -> Vec<MigrationRecord<'a>>
+where
+    DB: Database,
+{
+    let mut records = Vec::with_capacity(migration_plan.len());
+    for &migration in migration_plan {
+        let find_applied_migrations = applied_migrations
+            .iter()
+            .find(|&applied_migration| applied_migration == migration);
+
+        let record = if let Some(sqlx_migration) = find_applied_migrations {
+            MigrationRecord {
+                id: Some(sqlx_migration.id()),
+                app: migration.app(),
+                name: migration.name(),
+                status: if sqlx_migration.is_partially_applied() {
+                    MigrationStatus::PartiallyApplied
+                } else {
+                    MigrationStatus::Applied
+                },
+                applied_time: Some(sqlx_migration.applied_time()),
+                duration_ms: sqlx_migration
+                    .duration()
+                    .map(|duration| duration.as_millis()),
+                applied_by: sqlx_migration.applied_by(),
+                applied_operations: sqlx_migration.applied_operations(),
+                total_operations: migration.operations().len(),
+            }
+        } else {
+            MigrationRecord {
+                id: None,
+                app: migration.app(),
+                name: migration.name(),
+                status: if apply_plan.contains(&migration) {
+                    MigrationStatus::Pending
+                } else {
+                    MigrationStatus::Replaced
+                },
+                applied_time: None,
+                duration_ms: None,
+                applied_by: None,
+                applied_operations: None,
+                total_operations: migration.operations().len(),
+            }
+        };
+        records.push(record);
+    }
+    records
+}
+
 async fn list_migrations<DB>(
     connection: &mut <DB as Database>::Connection,
     migrator: Box<dyn Migrate<DB>>,
+    format: Format,
 ) -> Result<(), Error>
 where
     DB: Database,
 {
     let migration_plan = migrator.generate_migration_plan(connection, None).await?;
 
+    // listing migrations do not apply them so modified applied migration
+    // should not prevent listing
     let apply_plan = migrator
-        .generate_migration_plan(connection, Some(&Plan::apply_all()))
+        .generate_migration_plan(connection, Some(&Plan::apply_all().accept_checksum(true)))
         .await?;
     let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
 
-    let widths = [5, 10, 50, 10, 40];
+    let records = migration_records(&migration_plan, &apply_plan, &applied_migrations);
+
+    if format != Format::Text {
+        return print_records(format, &records);
+    }
+
+    let widths = [5, 10, 50, 10, 40, 12, 30];
     let full_width = widths.iter().sum::<usize>() + widths.len() * 3;
 
     let first_width = widths[0];
@@ -147,40 +677,388 @@ where
     let third_width = widths[2];
     let fourth_width = widths[3];
     let fifth_width = widths[4];
+    let sixth_width = widths[5];
+    let seventh_width = widths[6];
 
     println!(
         "{:^first_width$} | {:^second_width$} | {:^third_width$} | {:^fourth_width$} | \
-         {:^fifth_width$}",
-        "ID", "App", "Name", "Status", "Applied time"
+         {:^fifth_width$} | {:^sixth_width$} | {:^seventh_width$}",
+        "ID", "App", "Name", "Status", "Applied time", "Duration", "Applied by"
     );
 
     println!("{:^full_width$}", "-".repeat(full_width));
-    for migration in migration_plan {
-        let mut id = String::from("N/A");
-        let mut status = "\u{2717}";
-        let mut applied_time = String::from("N/A");
+    for record in &records {
+        println!(
+            "{:^first_width$} | {:^second_width$} | {:^third_width$} | {:^fourth_width$} | \
+             {:^fifth_width$} | {:^sixth_width$} | {:^seventh_width$}",
+            record
+                .id
+                .map_or_else(|| String::from("N/A"), |id| id.to_string()),
+            record.app,
+            record.name,
+            record.status.symbol(),
+            record.applied_time.unwrap_or("N/A"),
+            record
+                .duration_ms
+                .map_or_else(|| String::from("N/A"), |duration| format!("{duration} ms")),
+            record.applied_by.unwrap_or("N/A")
+        );
+    }
+    for record in &records {
+        if let Some(applied_operations) = record.applied_operations {
+            println!(
+                "{} : {} is partially applied ({applied_operations}/{} operations)",
+                record.app, record.name, record.total_operations
+            );
+        }
+    }
+    Ok(())
+}
 
-        let find_applied_migrations = applied_migrations
+#[derive(Parser, Debug)]
+struct History {
+    /// Only list history of provided app
+    #[arg(long)]
+    app: Option<String>,
+    /// Only list history of provided migration. Requires app options to be
+    /// present
+    #[arg(long, requires = "app")]
+    migration: Option<String>,
+}
+impl History {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        format: Format,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        migrator.ensure_migration_table_exists(connection).await?;
+        let history = migrator.fetch_history_from_db(connection).await?;
+        let records = history
             .iter()
-            .find(|&applied_migration| applied_migration == migration);
+            .filter(|history_row| {
+                self.app.as_ref().is_none_or(|app| app == history_row.app())
+                    && self
+                        .migration
+                        .as_ref()
+                        .is_none_or(|migration| migration == history_row.name())
+            })
+            .map(|history_row| {
+                HistoryRecord {
+                    id: history_row.id(),
+                    app: history_row.app(),
+                    name: history_row.name(),
+                    event: history_row.event(),
+                    event_time: history_row.event_time(),
+                    performed_by: history_row.performed_by(),
+                }
+            })
+            .collect::<Vec<_>>();
 
-        if let Some(sqlx_migration) = find_applied_migrations {
-            id = sqlx_migration.id().to_string();
-            status = "\u{2713}";
-            applied_time = sqlx_migration.applied_time().to_string();
-        } else if !apply_plan.contains(&migration) {
-            status = "\u{2194}";
+        if format != Format::Text {
+            return print_records(format, &records);
         }
 
+        let widths = [5, 10, 50, 12, 40, 30];
+        let full_width = widths.iter().sum::<usize>() + widths.len() * 3;
+
+        let first_width = widths[0];
+        let second_width = widths[1];
+        let third_width = widths[2];
+        let fourth_width = widths[3];
+        let fifth_width = widths[4];
+        let sixth_width = widths[5];
+
         println!(
             "{:^first_width$} | {:^second_width$} | {:^third_width$} | {:^fourth_width$} | \
-             {:^fifth_width$}",
-            id,
-            migration.app(),
-            migration.name(),
-            status,
-            applied_time
+             {:^fifth_width$} | {:^sixth_width$}",
+            "ID", "App", "Name", "Event", "Event time", "Performed by"
         );
+        println!("{:^full_width$}", "-".repeat(full_width));
+        for record in records {
+            println!(
+                "{:^first_width$} | {:^second_width$} | {:^third_width$} | {:^fourth_width$} | \
+                 {:^fifth_width$} | {:^sixth_width$}",
+                record.id,
+                record.app,
+                record.name,
+                record.event,
+                record.event_time,
+                record.performed_by.unwrap_or("N/A")
+            );
+        }
+        Ok(())
+    }
+}
+
+fn print_plan<'a, DB>(
+    format: Format,
+    migrations: impl Iterator<Item = &'a &'a Box<dyn Migration<DB>>>,
+    empty_message: &str,
+) -> Result<(), Error>
+where
+    DB: Database,
+{
+    let records = migrations
+        .map(|migration| {
+            PlanRecord {
+                app: migration.app(),
+                name: migration.name(),
+            }
+        })
+        .collect::<Vec<_>>();
+    if format != Format::Text {
+        return print_records(format, &records);
+    }
+    if records.is_empty() {
+        println!("{empty_message}");
+    } else {
+        let first_width = 10;
+        let second_width = 50;
+        let full_width = first_width + second_width + 3;
+        println!("{:^first_width$} | {:^second_width$}", "App", "Name");
+        println!("{:^full_width$}", "-".repeat(full_width));
+        for record in records {
+            println!(
+                "{:^first_width$} | {:^second_width$}",
+                record.app, record.name,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_dry_run(
+    format: Format,
+    dry_run_migrations: &[DryRunMigration],
+    empty_message: &str,
+) -> Result<(), Error> {
+    if format != Format::Text {
+        let records = dry_run_migrations
+            .iter()
+            .map(|migration| {
+                DryRunRecord {
+                    app: migration.app(),
+                    name: migration.name(),
+                    executed: migration.executed(),
+                    statements: migration.statements(),
+                }
+            })
+            .collect::<Vec<_>>();
+        return print_records(format, &records);
+    }
+    if dry_run_migrations.is_empty() {
+        println!("{empty_message}");
+    }
+    for migration in dry_run_migrations {
+        let status = if migration.executed() {
+            "executed and rolled back"
+        } else {
+            "not executed"
+        };
+        println!("{} : {} ({status})", migration.app(), migration.name());
+        for statement in migration.statements() {
+            println!(
+                "    {}",
+                statement.as_deref().unwrap_or("<statement not available>")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_run_result<'a, DB>(
+    format: Format,
+    migrations: impl Iterator<Item = &'a &'a Box<dyn Migration<DB>>>,
+    event: HistoryEvent,
+    success_message: &str,
+) -> Result<(), Error>
+where
+    DB: Database,
+{
+    if format == Format::Text {
+        println!("{success_message}");
+        return Ok(());
+    }
+    print_records(format, &event_records(migrations, event))
+}
+
+/// Create record of event which is added for migrations which are run
+fn event_records<'a, DB>(
+    migrations: impl Iterator<Item = &'a &'a Box<dyn Migration<DB>>>,
+    event: HistoryEvent,
+) -> Vec<EventRecord<'a>>
+where
+    DB: Database,
+{
+    migrations
+        .map(|migration| {
+            EventRecord {
+                app: migration.app(),
+                name: migration.name(),
+                event: event.as_str(),
+            }
+        })
+        .collect()
+}
+
+/// Print report of running plan which continues on error
+fn print_run_report(format: Format, report: &RunReport, empty_message: &str) -> Result<(), Error> {
+    let records = run_records(report);
+
+    if format != Format::Text {
+        return print_records(format, &records);
+    }
+
+    if records.is_empty() {
+        println!("{empty_message}");
+        return Ok(());
+    }
+
+    let widths = [10, 50, 10];
+    let full_width = widths.iter().sum::<usize>() + widths.len() * 3;
+
+    let first_width = widths[0];
+    let second_width = widths[1];
+    let third_width = widths[2];
+
+    println!(
+        "{:^first_width$} | {:^second_width$} | {:^third_width$}",
+        "App", "Name", "Status"
+    );
+    println!("{:^full_width$}", "-".repeat(full_width));
+    for record in records {
+        println!(
+            "{:^first_width$} | {:^second_width$} | {:^third_width$}",
+            record.app,
+            record.name,
+            record.status.as_str()
+        );
+    }
+    Ok(())
+}
+
+/// Create record of status of migrations in report of plan
+fn run_records(report: &RunReport) -> Vec<RunRecord<'_>> {
+    report
+        .succeeded()
+        .iter()
+        .map(|(app, name)| (app, name, RunStatus::Succeeded))
+        .chain(
+            report
+                .failed()
+                .iter()
+                .map(|(app, name, _)| (app, name, RunStatus::Failed)),
+        )
+        .chain(
+            report
+                .skipped()
+                .iter()
+                .map(|(app, name)| (app, name, RunStatus::Skipped)),
+        )
+        .map(|(app, name, status)| RunRecord { app, name, status })
+        .collect()
+}
+
+/// Run plan which continues on error and print its report. Error is returned
+/// if any migration of plan fails
+async fn run_plan_with_report<DB>(
+    connection: &mut CommandConnection<'_, DB>,
+    migrator: &dyn Migrate<DB>,
+    plan: &Plan,
+    format: Format,
+    empty_message: &str,
+) -> Result<(), Error>
+where
+    DB: Database,
+{
+    let report = connection.run_with_report(migrator, plan).await?;
+    for (_, _, error) in report.failed() {
+        print_run_error(error)?;
+    }
+    print_run_report(format, &report, empty_message)?;
+    if !report.is_success() {
+        return Err(Error::MigrationsFailed {
+            report: Box::new(report),
+        });
+    }
+    Ok(())
+}
+
+/// Run plan and print details of failed operation. Error is still returned so
+/// that it is reported by caller
+async fn run_plan<DB>(
+    connection: &mut CommandConnection<'_, DB>,
+    migrator: &dyn Migrate<DB>,
+    plan: &Plan,
+) -> Result<(), Error>
+where
+    DB: Database,
+{
+    let result = connection
+        .run_with_report(migrator, plan)
+        .await
+        .and_then(|report| {
+            if report.is_success() {
+                Ok(())
+            } else {
+                Err(Error::MigrationsFailed {
+                    report: Box::new(report),
+                })
+            }
+        });
+    if let Err(error) = result {
+        print_run_error(&error)?;
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Print details of failed operation to stderr so that failing migration and
+/// operation can be identified irrespective of output format
+fn print_run_error(error: &Error) -> Result<(), Error> {
+    let mut writer = std::io::stderr().lock();
+    write_run_error(&mut writer, error)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write details of failed operation along with chain of errors which caused
+/// failure to writer
+fn write_run_error(writer: &mut dyn std::io::Write, error: &Error) -> Result<(), Error> {
+    let Error::OperationFailed {
+        app,
+        name,
+        operation_index,
+        direction,
+        rolled_back,
+        source,
+    } = error
+    else {
+        return Ok(());
+    };
+    writeln!(writer, "Migration {app} : {name} failed")?;
+    writeln!(
+        writer,
+        "    Operation: {operation_index} ({})",
+        direction.as_str()
+    )?;
+    if *rolled_back {
+        writeln!(writer, "    Rolled back: yes")?;
+    } else {
+        writeln!(
+            writer,
+            "    Rolled back: no, operations run before failed operation remain applied"
+        )?;
+    }
+    writeln!(writer, "    Error: {source}")?;
+    let mut cause = std::error::Error::source(source.as_ref());
+    while let Some(error) = cause {
+        writeln!(writer, "    Caused by: {error}")?;
+        cause = error.source();
     }
     Ok(())
 }
@@ -188,6 +1066,10 @@ where
 #[derive(Parser, Debug)]
 #[expect(clippy::struct_excessive_bools)]
 struct Apply {
+    /// Accept changed checksum of applied migrations and store their current
+    /// checksum instead of failing
+    #[arg(long)]
+    accept_checksum: bool,
     /// App name up to which migration needs to be applied. If migration option
     /// is also present than only till migration is applied
     #[arg(long)]
@@ -195,9 +1077,17 @@ struct Apply {
     /// Check for pending migration
     #[arg(long)]
     check: bool,
+    /// Continue applying migrations which do not depend upon failed
+    /// migration when a migration fails
+    #[arg(long)]
+    continue_on_error: bool,
     /// Number of migration to apply. Conflicts with app args
     #[arg(long, conflicts_with = "app")]
     count: Option<usize>,
+    /// Show statements which would be executed by migrations without
+    /// applying them. Conflicts with fake args
+    #[arg(long, conflicts_with = "fake")]
+    dry_run: bool,
     /// Make migration applied without running migration operations
     #[arg(long)]
     fake: bool,
@@ -205,6 +1095,10 @@ struct Apply {
     /// destructible
     #[arg(long)]
     force: bool,
+    /// Number of seconds to wait for acquiring migration lock before failing.
+    /// By default lock timeout of migrator is used
+    #[arg(long, value_name = "SECONDS")]
+    lock_timeout: Option<u64>,
     /// Apply migration till provided migration. Requires app options to be
     /// present
     #[arg(long, requires = "app")]
@@ -212,12 +1106,17 @@ struct Apply {
     /// Show plan
     #[arg(long)]
     plan: bool,
+    /// Apply all migrations of plan in single transaction. Conflicts with
+    /// continue on error args
+    #[arg(long, conflicts_with = "continue_on_error")]
+    single_transaction: bool,
 }
 impl Apply {
     async fn run<DB>(
         &self,
-        connection: &mut <DB as Database>::Connection,
+        connection: &mut CommandConnection<'_, DB>,
         migrator: Box<dyn Migrate<DB>>,
+        format: Format,
     ) -> Result<(), Error>
     where
         DB: Database,
@@ -230,7 +1129,12 @@ impl Apply {
         } else {
             plan = Plan::apply_all();
         }
-        let plan = plan.fake(self.fake);
+        let plan = plan
+            .fake(self.fake)
+            .lock_timeout(self.lock_timeout.map(Duration::from_secs))
+            .continue_on_error(self.continue_on_error)
+            .single_transaction(self.single_transaction)
+            .accept_checksum(self.accept_checksum);
         let migrations = migrator
             .generate_migration_plan(connection, Some(&plan))
             .await?;
@@ -238,46 +1142,59 @@ impl Apply {
             return Err(Error::PendingMigrationPresent);
         }
         if self.plan {
-            if migrations.is_empty() {
-                println!("No migration exists for applying");
-            } else {
-                let first_width = 10;
-                let second_width = 50;
-                let full_width = first_width + second_width + 3;
-                println!("{:^first_width$} | {:^second_width$}", "App", "Name");
-                println!("{:^full_width$}", "-".repeat(full_width));
-                for migration in migrations {
-                    println!(
-                        "{:^first_width$} | {:^second_width$}",
-                        migration.app(),
-                        migration.name(),
-                    );
-                }
-            }
+            print_plan(
+                format,
+                migrations.iter(),
+                "No migration exists for applying",
+            )?;
+        } else if self.dry_run {
+            let dry_run_migrations = migrator.dry_run(connection, &plan).await?;
+            print_dry_run(
+                format,
+                &dry_run_migrations,
+                "No migration exists for applying",
+            )?;
         } else {
             let destructible_migrations = migrations
                 .iter()
                 .filter(|m| m.operations().iter().any(|o| o.is_destructible()))
                 .collect::<Vec<_>>();
-            if !self.force && !destructible_migrations.is_empty() && !self.fake {
-                let mut input = String::new();
-                println!(
-                    "Do you want to apply destructible migrations {} (y/N)",
-                    destructible_migrations.len()
-                );
-                for (position, migration) in destructible_migrations.iter().enumerate() {
-                    println!("{position}. {} : {}", migration.app(), migration.name());
-                }
-                std::io::stdout().flush()?;
-                std::io::stdin().read_line(&mut input)?;
-                let input_trimmed = input.trim().to_ascii_lowercase();
-                // If answer is not y or yes then return
-                if !["y", "yes"].contains(&input_trimmed.as_str()) {
-                    return Ok(());
-                }
+            if !self.force
+                && !destructible_migrations.is_empty()
+                && !self.fake
+                && !confirm(
+                    format,
+                    &format!(
+                        "Do you want to apply destructible migrations {}",
+                        destructible_migrations.len()
+                    ),
+                    destructible_migrations.into_iter(),
+                )?
+            {
+                return Ok(());
+            }
+            if self.continue_on_error {
+                return run_plan_with_report(
+                    connection,
+                    migrator.as_ref(),
+                    &plan,
+                    format,
+                    "No migration exists for applying",
+                )
+                .await;
             }
-            migrator.run(connection, &plan).await?;
-            println!("Successfully applied migrations according to plan");
+            run_plan(connection, migrator.as_ref(), &plan).await?;
+            let event = if self.fake {
+                HistoryEvent::FakeApply
+            } else {
+                HistoryEvent::Apply
+            };
+            print_run_result(
+                format,
+                migrations.iter(),
+                event,
+                "Successfully applied migrations according to plan",
+            )?;
         }
         Ok(())
     }
@@ -293,15 +1210,27 @@ struct Revert {
     /// alongside migration options than only till migration is reverted
     #[arg(long)]
     app: Option<String>,
+    /// Continue reverting migrations upon which failed migration do not depend
+    /// when a migration fails
+    #[arg(long)]
+    continue_on_error: bool,
     /// Number of migration to revert. Conflicts with all and app args
     #[arg(long, conflicts_with_all = ["all", "app"])]
     count: Option<usize>,
+    /// Show statements which would be executed by migrations without
+    /// reverting them. Conflicts with fake args
+    #[arg(long, conflicts_with = "fake")]
+    dry_run: bool,
     /// Make migration reverted without running revert operation
     #[arg(long)]
     fake: bool,
     /// Force run revert operation without asking question
     #[arg(long)]
     force: bool,
+    /// Number of seconds to wait for acquiring migration lock before failing.
+    /// By default lock timeout of migrator is used
+    #[arg(long, value_name = "SECONDS")]
+    lock_timeout: Option<u64>,
     /// Revert migration till provided migration. Requires app options to be
     /// present
     #[arg(long, requires = "app")]
@@ -309,12 +1238,17 @@ struct Revert {
     /// Show plan
     #[arg(long)]
     plan: bool,
+    /// Revert all migrations of plan in single transaction. Conflicts with
+    /// continue on error args
+    #[arg(long, conflicts_with = "continue_on_error")]
+    single_transaction: bool,
 }
 impl Revert {
     async fn run<DB>(
         &self,
-        connection: &mut <DB as Database>::Connection,
+        connection: &mut CommandConnection<'_, DB>,
         migrator: Box<dyn Migrate<DB>>,
+        format: Format,
     ) -> Result<(), Error>
     where
         DB: Database,
@@ -329,48 +1263,236 @@ impl Revert {
         } else {
             plan = Plan::revert_count(1);
         }
-        let plan = plan.fake(self.fake);
+        let plan = plan
+            .fake(self.fake)
+            .lock_timeout(self.lock_timeout.map(Duration::from_secs))
+            .continue_on_error(self.continue_on_error)
+            .single_transaction(self.single_transaction);
         let revert_migrations = migrator
             .generate_migration_plan(connection, Some(&plan))
             .await?;
 
         if self.plan {
-            if revert_migrations.is_empty() {
-                println!("No migration exists for reverting");
+            print_plan(
+                format,
+                revert_migrations.iter(),
+                "No migration exists for reverting",
+            )?;
+        } else if self.dry_run {
+            let dry_run_migrations = migrator.dry_run(connection, &plan).await?;
+            print_dry_run(
+                format,
+                &dry_run_migrations,
+                "No migration exists for reverting",
+            )?;
+        } else {
+            if !self.force
+                && !revert_migrations.is_empty()
+                && !self.fake
+                && !confirm(
+                    format,
+                    &format!(
+                        "Do you want to revert {} migrations",
+                        revert_migrations.len()
+                    ),
+                    revert_migrations.iter(),
+                )?
+            {
+                return Ok(());
+            }
+            if self.continue_on_error {
+                return run_plan_with_report(
+                    connection,
+                    migrator.as_ref(),
+                    &plan,
+                    format,
+                    "No migration exists for reverting",
+                )
+                .await;
+            }
+            run_plan(connection, migrator.as_ref(), &plan).await?;
+            let event = if self.fake {
+                HistoryEvent::FakeRevert
             } else {
-                let first_width = 10;
-                let second_width = 50;
-                let full_width = first_width + second_width + 3;
-                println!("{:^first_width$} | {:^second_width$}", "App", "Name");
-                println!("{:^full_width$}", "-".repeat(full_width));
-                for migration in revert_migrations {
-                    println!(
-                        "{:^first_width$} | {:^second_width$}",
-                        migration.app(),
-                        migration.name(),
-                    );
+                HistoryEvent::Revert
+            };
+            print_run_result(
+                format,
+                revert_migrations.iter(),
+                event,
+                "Successfully reverted migrations according to plan",
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct SyncMigrations {
+    /// Show report of sync without adding migrations to migration table
+    #[arg(long)]
+    dry_run: bool,
+}
+impl SyncMigrations {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        old_migrator: &dyn OldMigrator<DB>,
+        format: Format,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        migrator.ensure_migration_table_exists(connection).await?;
+        let report = crate::sync::sync_old_migrator(
+            migrator.as_ref(),
+            connection,
+            old_migrator,
+            self.dry_run,
+        )
+        .await?;
+        print_sync_report(format, &report, self.dry_run)
+    }
+}
+
+/// Print report of sync
+fn print_sync_report(format: Format, report: &SyncReport, dry_run: bool) -> Result<(), Error> {
+    let inserted_status = if dry_run { "to_insert" } else { "inserted" };
+    let records = report
+        .inserted()
+        .iter()
+        .map(|app_name| (app_name, inserted_status))
+        .chain(
+            report
+                .already_applied()
+                .iter()
+                .map(|app_name| (app_name, "already_applied")),
+        )
+        .chain(
+            report
+                .unknown()
+                .iter()
+                .map(|app_name| (app_name, "unknown")),
+        )
+        .map(|((app, name), status)| SyncRecord { app, name, status })
+        .collect::<Vec<_>>();
+
+    if format != Format::Text {
+        return print_records(format, &records);
+    }
+
+    if records.is_empty() {
+        println!("No migration is applied by old migrator");
+        return Ok(());
+    }
+
+    let widths = [10, 50, 16];
+    let full_width = widths.iter().sum::<usize>() + widths.len() * 3;
+
+    let first_width = widths[0];
+    let second_width = widths[1];
+    let third_width = widths[2];
+
+    println!(
+        "{:^first_width$} | {:^second_width$} | {:^third_width$}",
+        "App", "Name", "Status"
+    );
+    println!("{:^full_width$}", "-".repeat(full_width));
+    for record in records {
+        println!(
+            "{:^first_width$} | {:^second_width$} | {:^third_width$}",
+            record.app, record.name, record.status
+        );
+    }
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+struct LockCommand {
+    #[command(subcommand)]
+    sub_command: LockSubCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum LockSubCommand {
+    /// Show whether migration lock is held and by which session
+    Status,
+    /// Forcibly release migration lock. Session holding lock is terminated
+    /// for postgres and mysql
+    Release(ReleaseLock),
+}
+
+#[derive(Parser, Debug)]
+struct ReleaseLock {
+    /// Force release lock without asking question
+    #[arg(long)]
+    force: bool,
+}
+
+impl LockCommand {
+    async fn run<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrator: Box<dyn Migrate<DB>>,
+        format: Format,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+    {
+        let holder = migrator.fetch_lock_holder(connection).await?;
+        match &self.sub_command {
+            LockSubCommand::Status => {
+                let record = LockRecord {
+                    held: holder.is_some(),
+                    session: holder.as_ref().map(LockHolder::session),
+                    since: holder.as_ref().and_then(LockHolder::since),
+                };
+                if format != Format::Text {
+                    return print_records(format, &[record]);
                 }
-            }
-        } else {
-            if !self.force && !revert_migrations.is_empty() && !self.fake {
-                let mut input = String::new();
-                println!(
-                    "Do you want to revert {} migrations (y/N)",
-                    revert_migrations.len()
-                );
-                for (position, migration) in revert_migrations.iter().enumerate() {
-                    println!("{position}. {} : {}", migration.app(), migration.name());
+                match record.session {
+                    Some(session) => {
+                        println!(
+                            "Migration lock is held by session {session} since {}",
+                            record.since.unwrap_or("N/A")
+                        );
+                    }
+                    None => println!("Migration lock is not held"),
                 }
-                std::io::stdout().flush()?;
-                std::io::stdin().read_line(&mut input)?;
-                let input_trimmed = input.trim().to_ascii_lowercase();
-                // If answer is not y or yes then return
-                if !["y", "yes"].contains(&input_trimmed.as_str()) {
+            }
+            LockSubCommand::Release(release) => {
+                let Some(holder) = holder else {
+                    if format == Format::Text {
+                        println!("Migration lock is not held");
+                    }
+                    return print_records(format, &[StatusRecord { status: "not_held" }]);
+                };
+                if !release.force
+                    && !confirm::<DB>(
+                        format,
+                        &format!(
+                            "Do you want to release migration lock held by session {}",
+                            holder.session()
+                        ),
+                        std::iter::empty(),
+                    )?
+                {
                     return Ok(());
                 }
+                // only session which is confirmed is released so lock acquired
+                // by other session in between is not released
+                let (status, message) =
+                    if migrator.force_unlock(connection, holder.session()).await? {
+                        ("released", "Released migration lock")
+                    } else {
+                        ("not_held", "Migration lock is not held")
+                    };
+                if format == Format::Text {
+                    println!("{message}");
+                }
+                print_records(format, &[StatusRecord { status }])?;
             }
-            migrator.run(connection, &plan).await?;
-            println!("Successfully reverted migrations according to plan");
         }
         Ok(())
     }
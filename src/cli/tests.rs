@@ -0,0 +1,343 @@
+use clap::Parser as _;
+use sqlx::{Sqlite, SqlitePool};
+
+use super::{
+    Format, MigrationCommand, MigrationStatus, Record, RunStatus, event_records, migration_records,
+    run_records, write_records, write_run_error,
+};
+use crate::error::Error;
+use crate::migration::{HistoryEvent, Migration};
+use crate::migrator::{DatabaseOperation as _, Info as _, Migrate as _, Migrator, Plan};
+use crate::operation::Operation;
+use crate::vec_box;
+
+macro_rules! migration {
+    ($op:ty, $name:literal, $parents:expr, $up:literal, $down:literal) => {
+        impl Migration<Sqlite> for $op {
+            fn app(&self) -> &str {
+                "cli"
+            }
+
+            fn name(&self) -> &str {
+                $name
+            }
+
+            fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+                $parents
+            }
+
+            fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+                vec_box![($up, $down)]
+            }
+        }
+    };
+}
+
+struct A;
+migration!(
+    A,
+    "a",
+    vec_box!(),
+    "CREATE TABLE a (id INTEGER)",
+    "DROP TABLE a"
+);
+
+// name contains comma and quote so csv field needs to be quoted
+struct B;
+migration!(
+    B,
+    "b, \"failing\"",
+    vec_box!(A),
+    "SELECT * FROM missing_table",
+    ""
+);
+
+struct C;
+migration!(
+    C,
+    "c",
+    vec_box!(B),
+    "CREATE TABLE c (id INTEGER)",
+    "DROP TABLE c"
+);
+
+fn migrator() -> Migrator<Sqlite> {
+    let mut migrator = Migrator::default().set_applied_by("tester");
+    migrator.add_migrations(vec_box![A, B, C]).unwrap();
+    migrator
+}
+
+fn write<T>(format: Format, records: &[T]) -> String
+where
+    T: Record,
+{
+    let mut output = Vec::new();
+    write_records(&mut output, format, records).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+/// Parse csv document into rows of fields
+fn parse_csv(document: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut characters = document.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (quoted, character) {
+            (true, '"') if characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            }
+            (_, '"') => quoted = !quoted,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (_, character) => field.push(character),
+        }
+    }
+    rows
+}
+
+/// Assert that json, yaml and csv document of records can be read back to
+/// same records
+fn assert_round_trip<T>(records: &[T])
+where
+    T: Record,
+{
+    let expected = serde_json::to_value(records).unwrap();
+
+    let json = write(Format::Json, records);
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+        expected
+    );
+
+    let yaml = write(Format::Yaml, records);
+    assert_eq!(
+        serde_yaml_ng::from_str::<serde_json::Value>(&yaml).unwrap(),
+        expected
+    );
+
+    let csv = write(Format::Csv, records);
+    let mut rows = parse_csv(&csv).into_iter();
+    assert_eq!(rows.next().unwrap(), T::FIELDS);
+    let expected_rows = expected
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|record| {
+            T::FIELDS
+                .iter()
+                .map(|field| {
+                    match &record[field] {
+                        serde_json::Value::Null => String::new(),
+                        serde_json::Value::String(string) => string.clone(),
+                        other => other.to_string(),
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(rows.collect::<Vec<_>>(), expected_rows);
+
+    assert!(write(Format::Text, records).is_empty());
+}
+
+#[tokio::test]
+async fn list_output_round_trip() {
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let migrator = migrator();
+    migrator
+        .run(&mut conn, &Plan::apply_count(1))
+        .await
+        .unwrap();
+
+    let migration_plan = migrator
+        .generate_migration_plan(&mut conn, None)
+        .await
+        .unwrap();
+    let apply_plan = migrator
+        .generate_migration_plan(&mut conn, Some(&Plan::apply_all()))
+        .await
+        .unwrap();
+    let applied_migrations = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    let records = migration_records(&migration_plan, &apply_plan, &applied_migrations);
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| (record.name, record.status))
+            .collect::<Vec<_>>(),
+        [
+            ("a", MigrationStatus::Applied),
+            ("b, \"failing\"", MigrationStatus::Pending),
+            ("c", MigrationStatus::Pending)
+        ]
+    );
+    assert_eq!(records[0].applied_by, Some("tester"));
+    assert_round_trip(&records);
+
+    let json = serde_json::from_str::<serde_json::Value>(&write(Format::Json, &records)).unwrap();
+    assert_eq!(json[0]["status"], "applied");
+    assert_eq!(json[1]["status"], "pending");
+}
+
+#[tokio::test]
+async fn apply_output_round_trip() {
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let migrator = migrator();
+
+    let plan = Plan::apply_count(1);
+    let migrations = migrator
+        .generate_migration_plan(&mut conn, Some(&plan))
+        .await
+        .unwrap();
+    migrator.run(&mut conn, &plan).await.unwrap();
+    let records = event_records(migrations.iter(), HistoryEvent::Apply);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].event, HistoryEvent::Apply.as_str());
+    assert_round_trip(&records);
+
+    let report = migrator
+        .run_with_report(&mut conn, &Plan::apply_all().continue_on_error(true))
+        .await
+        .unwrap();
+    let records = run_records(&report);
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| (record.name, record.status))
+            .collect::<Vec<_>>(),
+        [
+            ("b, \"failing\"", RunStatus::Failed),
+            ("c", RunStatus::Skipped)
+        ]
+    );
+    assert_round_trip(&records);
+
+    let yaml =
+        serde_yaml_ng::from_str::<serde_json::Value>(&write(Format::Yaml, &records)).unwrap();
+    assert_eq!(yaml[0]["status"], "failed");
+    assert_eq!(yaml[1]["status"], "skipped");
+}
+
+#[tokio::test]
+async fn revert_continue_on_error() {
+    assert!(
+        MigrationCommand::try_parse_from(["migrator", "revert", "--continue-on-error"]).is_ok()
+    );
+    assert!(
+        MigrationCommand::try_parse_from([
+            "migrator",
+            "revert",
+            "--continue-on-error",
+            "--single-transaction"
+        ])
+        .is_err()
+    );
+
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let migrator = migrator();
+    migrator
+        .run(&mut conn, &Plan::apply_count(1))
+        .await
+        .unwrap();
+    // revert of migration fails since table is already dropped
+    sqlx::query("DROP TABLE a")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    let report = migrator
+        .run_with_report(&mut conn, &Plan::revert_all().continue_on_error(true))
+        .await
+        .unwrap();
+    let records = run_records(&report);
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| (record.name, record.status))
+            .collect::<Vec<_>>(),
+        [("a", RunStatus::Failed)]
+    );
+    assert_round_trip(&records);
+}
+
+#[tokio::test]
+async fn run_error_shows_database_error() {
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let migrator = migrator();
+    let error = migrator
+        .run(&mut conn, &Plan::apply_all())
+        .await
+        .unwrap_err();
+    let mut output = Vec::new();
+    write_run_error(&mut output, &error).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Migration cli : b, \"failing\" failed"));
+    assert!(output.contains("Operation: 0 (apply)"));
+    assert!(output.contains("no such table: missing_table"));
+}
+
+#[tokio::test]
+async fn cancelled_run_with_pool_closes_connection() {
+    struct PendingOperation;
+    #[async_trait::async_trait]
+    impl Operation<Sqlite> for PendingOperation {
+        async fn up(&self, _connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+            std::future::pending().await
+        }
+    }
+    struct Pending;
+    impl Migration<Sqlite> for Pending {
+        fn app(&self) -> &'static str {
+            "cli"
+        }
+
+        fn name(&self) -> &'static str {
+            "pending"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!(A)
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(PendingOperation)
+        }
+    }
+    let sqlite = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let mut migrator = Migrator::default();
+    migrator.add_migrations(vec_box!(A, Pending)).unwrap();
+    let command = MigrationCommand::try_parse_from(["migrator", "apply"]).unwrap();
+    assert!(
+        tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            command.run_with_pool(&sqlite, Box::new(migrator))
+        )
+        .await
+        .is_err()
+    );
+
+    // connection holding lock is closed so in memory database is recreated for
+    // next connection instead of lock still being held
+    let (count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name = '_sqlx_migrator_migrations_lock'",
+    )
+    .fetch_one(&sqlite)
+    .await
+    .unwrap();
+    assert_eq!(count, 0);
+}
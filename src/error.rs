@@ -11,9 +11,16 @@ pub enum Error {
     #[error(transparent)]
     Box(#[from] Box<dyn std::error::Error + Send + Sync>),
     /// Error type created from error raised by std input output
-    #[cfg(feature = "cli")]
     #[error(transparent)]
     StdIo(#[from] std::io::Error),
+    /// Error type created from error raised by serde json
+    #[cfg(feature = "cli")]
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+    /// Error type created from error raised by serde yaml
+    #[cfg(feature = "cli")]
+    #[error(transparent)]
+    SerdeYaml(#[from] serde_yaml_ng::Error),
     /// Error generated during planning state
     #[error("plan error: {message}")]
     PlanError {
@@ -31,6 +38,10 @@ pub enum Error {
     #[cfg(feature = "cli")]
     #[error("applied migrations exists. Revert all using revert subcommand")]
     AppliedMigrationExists,
+    /// Error when sync subcommand is used without providing old migrator
+    #[cfg(feature = "cli")]
+    #[error("old migrator is not provided. Run cli with old migrator to sync")]
+    OldMigratorNotProvided,
     /// Error when unsupported database is used as any database
     #[error("unsupported database")]
     UnsupportedDatabase,
@@ -49,8 +60,163 @@ pub enum Error {
         /// Migration name
         name: String,
     },
+    /// Error raised when checksum of already applied migration do not match
+    /// with checksum of current migration while applying migrations. Changed
+    /// checksum can be accepted using
+    /// [`crate::migrator::Plan::accept_checksum`]
+    #[error(
+        "checksum mismatch found for applied migration {app} - {name}: stored {stored_checksum}, \
+         current {current_checksum}"
+    )]
+    ChecksumMismatch {
+        /// Migration application name
+        app: String,
+        /// Migration name
+        name: String,
+        /// Checksum stored when migration was applied
+        stored_checksum: String,
+        /// Checksum of current migration
+        current_checksum: String,
+    },
+    /// Error raised when sql migration is invalid
+    #[error("invalid sql migration {app} - {name}: {message}")]
+    InvalidSqlMigration {
+        /// Migration application name
+        app: String,
+        /// Migration name
+        name: String,
+        /// Message for error
+        message: String,
+    },
+    /// Error raised during dry run when operation is executed but its
+    /// statements are neither recorded nor exposed by operation
+    #[error(
+        "statements of operation {operation_index} of migration {app} - {name} cannot be captured \
+         during dry run"
+    )]
+    DryRunStatementsNotCaptured {
+        /// Migration application name
+        app: String,
+        /// Migration name
+        name: String,
+        /// Zero based position of operation in operations of migration
+        operation_index: usize,
+    },
+    /// Error raised when migration of old migrator is left in dirty state
+    #[error("old migrator migration {version} is dirty")]
+    DirtyOldMigration {
+        /// Version of dirty migration
+        version: String,
+    },
+    /// Error raised when migration lock cannot be acquired within timeout
+    #[error("failed to acquire migration lock within {timeout:?}")]
+    LockTimeout {
+        /// Timeout used for acquiring lock
+        timeout: std::time::Duration,
+    },
+    /// Error raised when migration lock is no longer held by migrator since
+    /// other migrator released lock after it was not refreshed within lock
+    /// expiry
+    #[error("migration lock is no longer held by migrator")]
+    LockLost,
+    /// Error raised when migration table cannot be dropped since migration
+    /// lock is held by other migrator
+    #[error("migration lock is held by {session}")]
+    LockHeld {
+        /// Session which holds migration lock
+        session: String,
+    },
+    /// Error raised when target migrator returns different number of export
+    /// status than number of migrations provided to it during export
+    #[error("target migrator returned {statuses} export status for {migrations} migrations")]
+    ExportStatusMismatch {
+        /// Number of migrations provided to target migrator
+        migrations: usize,
+        /// Number of export status returned by target migrator
+        statuses: usize,
+    },
+    /// Error raised when releasing migration lock fails after work done while
+    /// holding lock failed
+    #[error("{error}; additionally failed to release migration lock: {unlock_error}")]
+    LockReleaseFailed {
+        /// Error raised while holding lock
+        error: Box<Error>,
+        /// Error raised while releasing lock
+        unlock_error: Box<Error>,
+    },
+    /// Error raised when operation of migration fails while applying or
+    /// reverting migration
+    #[error(
+        "failed to {} operation {operation_index} of migration {app} - {name}{}",
+        .direction.as_str(),
+        if *.rolled_back { " (rolled back)" } else { "" }
+    )]
+    OperationFailed {
+        /// Migration application name
+        app: String,
+        /// Migration name
+        name: String,
+        /// Zero based position of failed operation in operations of migration
+        operation_index: usize,
+        /// Direction in which operation was run
+        direction: crate::migration::Direction,
+        /// Whether transaction of migration was rolled back. Non atomic
+        /// migration is not rolled back so its operations which are run
+        /// before failed operation remain applied
+        rolled_back: bool,
+        /// Error raised by operation
+        source: Box<Error>,
+    },
+    /// Error raised when database cancelled statement of operation since it
+    /// do not complete within timeout of operation
+    #[error("operation did not complete within {timeout:?}")]
+    OperationTimeout {
+        /// Timeout used for running operation
+        timeout: std::time::Duration,
+    },
+    /// Error raised when operation is cancelled by migrator since it do not
+    /// complete within its timeout. Statement of operation can still be running
+    /// on database so connection is not reused, connection acquired from pool
+    /// is closed while borrowed connection should be closed by caller.
+    /// Migration lock held by session of connection is released once
+    /// connection is closed
+    #[error("operation cancelled after {timeout:?}, connection is discarded")]
+    OperationCancelled {
+        /// Timeout used for running operation
+        timeout: std::time::Duration,
+    },
+    /// Error raised when migrations failed while running plan which continues
+    /// on error
+    #[error("{} migrations failed to run", report.failed().len())]
+    MigrationsFailed {
+        /// Report of running plan
+        report: Box<crate::migrator::RunReport>,
+    },
+    /// Error raised when migrator do not support inspecting or forcibly
+    /// releasing migration lock
+    #[error("migrator does not support inspecting or releasing migration lock")]
+    LockIntrospectionUnsupported,
     /// Error raised when virtual migration is invalid virtual migration is
     /// invalid if it have any fields present expect app name and migration name
     #[error("invalid virtual migration")]
     InvalidVirtualMigration,
 }
+
+impl Error {
+    /// Whether error is raised after operation is cancelled while its
+    /// statement can still be running so connection should not be reused.
+    ///
+    /// Migration lock is not released using such connection. Connection
+    /// acquired from pool by migrator is closed automatically while borrowed
+    /// connection must be closed by caller so that statement is stopped and
+    /// lock held by its session is released
+    #[must_use]
+    pub fn is_connection_discarded(&self) -> bool {
+        match self {
+            Self::OperationCancelled { .. } => true,
+            Self::OperationFailed { source, .. } => source.is_connection_discarded(),
+            Self::LockReleaseFailed { error, .. } => error.is_connection_discarded(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Module which is used to export applied migrations of `sqlx_migrator` to
+//! other migration schema
+//!
+//! It is reverse of [`Synchronize`](crate::sync::Synchronize) so applied
+//! migrations can be handed over to other migrator such as sqlx native
+//! migrator or another [`Migrator`] which uses different table prefix or
+//! schema
+
+use sqlx::{Connection as _, Database};
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+use sqlx::{Executor, IntoArguments};
+
+use crate::migration::{AppliedMigrationSqlRow, HistoryEvent};
+use crate::migrator::{
+    BorrowedConnection, BoxMigration, DatabaseOperation, LockGuard, catch_unwind,
+};
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+use crate::sync::ConnectionBackend as _;
+use crate::{Error, Info, Migration, Migrator};
+
+/// Status of migration after it is exported to target migrator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExportStatus {
+    /// Migration is added to target migrator
+    Exported,
+    /// Migration was already present in target migrator
+    AlreadyExported,
+    /// Migration cannot be represented in target migrator so it is skipped
+    Skipped,
+}
+
+/// Trait which is implemented for migrator to which applied migrations are
+/// exported
+#[async_trait::async_trait]
+pub trait TargetMigrator<DB>: Send + Sync
+where
+    DB: Database,
+{
+    /// Ensure table used by target migrator to track migrations is created
+    async fn ensure_target_table_exists(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error>;
+
+    /// Add applied migrations to target migrator. Applied migration row
+    /// contains information such as applied time stored by migrator. Returns
+    /// status of each migration in same order as provided migrations, export
+    /// fails if number of returned status differs from number of migrations
+    async fn add_applied_migrations(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrations: &[(&BoxMigration<DB>, &AppliedMigrationSqlRow)],
+    ) -> Result<Vec<ExportStatus>, Error>;
+
+    /// Lock target migrator so that migrations of target migrator are not
+    /// changed by other migrator while applied migrations are exported
+    ///
+    /// By default, target migrator is not locked
+    async fn lock_target(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Unlock target migrator locked using [`TargetMigrator::lock_target`]
+    ///
+    /// By default, nothing is done since target migrator is not locked
+    async fn unlock_target(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB, T> TargetMigrator<DB> for T
+where
+    DB: Database,
+    Self: DatabaseOperation<DB> + Send + Sync,
+{
+    async fn ensure_target_table_exists(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        self.ensure_migration_table_exists(connection).await
+    }
+
+    async fn add_applied_migrations(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migrations: &[(&BoxMigration<DB>, &AppliedMigrationSqlRow)],
+    ) -> Result<Vec<ExportStatus>, Error> {
+        let target_applied_migrations = self.fetch_applied_migration_from_db(connection).await?;
+        let mut statuses = Vec::with_capacity(migrations.len());
+        for (migration, applied_migration) in migrations {
+            if target_applied_migrations
+                .iter()
+                .any(|applied| applied == *migration)
+            {
+                statuses.push(ExportStatus::AlreadyExported);
+                continue;
+            }
+            self.add_migration_to_db_table_with_applied_time(
+                connection,
+                migration,
+                applied_migration.applied_time(),
+            )
+            .await?;
+            self.add_history_to_db_table(connection, migration, HistoryEvent::Export)
+                .await?;
+            statuses.push(ExportStatus::Exported);
+        }
+        Ok(statuses)
+    }
+
+    async fn lock_target(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        self.lock(connection).await
+    }
+
+    async fn unlock_target(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        self.unlock(connection).await
+    }
+}
+
+/// Function which maps app and name of migration to version, description and
+/// checksum of sqlx native migration. Migration mapped to `None` is skipped
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+type SqlxNativeMapper = Box<dyn Fn(&str, &str) -> Option<(i64, String, Vec<u8>)> + Send + Sync>;
+
+/// Target migrator which writes applied migrations to `_sqlx_migrations`
+/// table used by sqlx native migrator
+///
+/// Each migration is mapped to version, description and checksum of sqlx
+/// migration using provided mapper function. Checksum should be same as
+/// checksum calculated by sqlx for migration file otherwise sqlx reports
+/// migration as modified. Table of sqlx native migrator is not locked so
+/// export should not be run while sqlx native migrator is running
+///
+/// ```rust
+/// use sqlx_migrator::export::SqlxNativeTarget;
+///
+/// let target = SqlxNativeTarget::new(|_app, name| {
+///     let (version, description) = name.split_once('_')?;
+///     Some((version.parse().ok()?, description.to_string(), vec![]))
+/// });
+/// ```
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub struct SqlxNativeTarget {
+    table_name: String,
+    mapper: SqlxNativeMapper,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl SqlxNativeTarget {
+    /// Create new sqlx native target with mapper function which maps app and
+    /// name of migration to version, description and checksum of sqlx
+    /// migration
+    pub fn new<F>(mapper: F) -> Self
+    where
+        F: Fn(&str, &str) -> Option<(i64, String, Vec<u8>)> + Send + Sync + 'static,
+    {
+        Self {
+            table_name: "_sqlx_migrations".to_string(),
+            mapper: Box::new(mapper),
+        }
+    }
+
+    /// Set table name used by sqlx native migrator. By default
+    /// `_sqlx_migrations` table is used
+    #[must_use]
+    pub fn set_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = table_name.into();
+        self
+    }
+
+    /// Returns create table query and insert query for database backend
+    fn queries(&self, backend_name: &str) -> Result<(String, String), Error> {
+        let table_name = &self.table_name;
+        let (installed_on_type, checksum_type, values) = match backend_name {
+            #[cfg(feature = "postgres")]
+            <sqlx::Postgres as Database>::NAME => {
+                (
+                    "TIMESTAMPTZ NOT NULL DEFAULT now()",
+                    "BYTEA",
+                    "$1, $2, CAST($3 AS TIMESTAMPTZ), TRUE, $4, $5",
+                )
+            }
+            #[cfg(feature = "sqlite")]
+            <sqlx::Sqlite as Database>::NAME => {
+                (
+                    "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP",
+                    "BLOB",
+                    "$1, $2, $3, TRUE, $4, $5",
+                )
+            }
+            #[cfg(feature = "mysql")]
+            <sqlx::MySql as Database>::NAME => {
+                (
+                    "TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP",
+                    "BLOB",
+                    "?, ?, ?, TRUE, ?, ?",
+                )
+            }
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        let create_query = format!(
+            "CREATE TABLE IF NOT EXISTS {table_name} (
+            version BIGINT PRIMARY KEY,
+            description TEXT NOT NULL,
+            installed_on {installed_on_type},
+            success BOOLEAN NOT NULL,
+            checksum {checksum_type} NOT NULL,
+            execution_time BIGINT NOT NULL
+        )"
+        );
+        let insert_query = format!(
+            "INSERT INTO {table_name} (version, description, installed_on, success, checksum, \
+             execution_time) VALUES ({values})"
+        );
+        Ok((create_query, insert_query))
+    }
+
+    async fn create_table<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        backend_name: &str,
+    ) -> Result<(), Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    {
+        let (create_query, _) = self.queries(backend_name)?;
+        sqlx::query::<DB>(&create_query).execute(connection).await?;
+        Ok(())
+    }
+
+    async fn add_migrations<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        backend_name: &str,
+        migrations: &[(&BoxMigration<DB>, &AppliedMigrationSqlRow)],
+    ) -> Result<Vec<ExportStatus>, Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'r> (i64,): sqlx::FromRow<'r, <DB as Database>::Row>,
+        for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        for<'q> String: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        for<'q> Vec<u8>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        let (_, insert_query) = self.queries(backend_name)?;
+        let mut versions =
+            sqlx::query_as::<_, (i64,)>(&format!("SELECT version FROM {}", self.table_name))
+                .fetch_all(&mut *connection)
+                .await?
+                .into_iter()
+                .map(|(version,)| version)
+                .collect::<Vec<_>>();
+        let mut statuses = Vec::with_capacity(migrations.len());
+        for (migration, applied_migration) in migrations {
+            let Some((version, description, checksum)) =
+                (self.mapper)(migration.app(), migration.name())
+            else {
+                statuses.push(ExportStatus::Skipped);
+                continue;
+            };
+            if versions.contains(&version) {
+                statuses.push(ExportStatus::AlreadyExported);
+                continue;
+            }
+            // sqlx stores execution time in nanoseconds
+            let execution_time = applied_migration.duration().map_or(0, |duration| {
+                i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX)
+            });
+            sqlx::query::<DB>(&insert_query)
+                .bind(version)
+                .bind(description)
+                .bind(applied_migration.applied_time().to_string())
+                .bind(checksum)
+                .bind(execution_time)
+                .execute(&mut *connection)
+                .await?;
+            versions.push(version);
+            statuses.push(ExportStatus::Exported);
+        }
+        Ok(statuses)
+    }
+}
+
+/// Implement [`TargetMigrator`] for supported databases using `create_table`
+/// and `add_migrations` method of target migrator
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+macro_rules! impl_target_migrator {
+    ($migrator:ty) => {
+        #[cfg(feature = "postgres")]
+        impl_target_migrator!($migrator, sqlx::Postgres);
+        #[cfg(feature = "sqlite")]
+        impl_target_migrator!($migrator, sqlx::Sqlite);
+        #[cfg(feature = "mysql")]
+        impl_target_migrator!($migrator, sqlx::MySql);
+        #[cfg(all(
+            any(feature = "postgres", feature = "mysql", feature = "sqlite"),
+            feature = "any"
+        ))]
+        impl_target_migrator!($migrator, sqlx::Any);
+    };
+    ($migrator:ty, $db:ty) => {
+        #[async_trait::async_trait]
+        impl TargetMigrator<$db> for $migrator {
+            async fn ensure_target_table_exists(
+                &self,
+                connection: &mut <$db as Database>::Connection,
+            ) -> Result<(), Error> {
+                let backend_name = connection.backend().to_string();
+                self.create_table::<$db>(connection, &backend_name).await
+            }
+
+            async fn add_applied_migrations(
+                &self,
+                connection: &mut <$db as Database>::Connection,
+                migrations: &[(&Box<dyn Migration<$db>>, &AppliedMigrationSqlRow)],
+            ) -> Result<Vec<ExportStatus>, Error> {
+                let backend_name = connection.backend().to_string();
+                self.add_migrations::<$db>(connection, &backend_name, migrations)
+                    .await
+            }
+        }
+    };
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl_target_migrator!(SqlxNativeTarget);
+
+/// Report of export which lists applied migrations according to how they were
+/// handled by target migrator
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    exported: Vec<(String, String)>,
+    already_exported: Vec<(String, String)>,
+    skipped: Vec<(String, String)>,
+}
+
+impl ExportReport {
+    /// Returns app and name of migrations which are added to target migrator
+    #[must_use]
+    pub fn exported(&self) -> &[(String, String)] {
+        &self.exported
+    }
+
+    /// Returns app and name of migrations which were already present in target
+    /// migrator
+    #[must_use]
+    pub fn already_exported(&self) -> &[(String, String)] {
+        &self.already_exported
+    }
+
+    /// Returns app and name of migrations which cannot be represented in
+    /// target migrator or are only partially applied
+    #[must_use]
+    pub fn skipped(&self) -> &[(String, String)] {
+        &self.skipped
+    }
+}
+
+/// Trait which is implemented for exporting applied migrations of migrator to
+/// target migrator for a specific database
+#[async_trait::async_trait]
+pub trait Export<DB>: Info<DB> + DatabaseOperation<DB>
+where
+    DB: Database,
+{
+    /// Exports applied migrations of current migrator to target migrator
+    ///
+    /// Applied migrations are fetched from migration table and added to target
+    /// migrator along with their applied time. Target migrator table is
+    /// created if it do not exists. Both current migrator and target migrator
+    /// are locked while exporting. Returns report which lists exported,
+    /// already exported and skipped migrations. Similar to
+    /// [`Migrate::run`](crate::migrator::Migrate::run), if returned future is
+    /// cancelled migration lock remains held by session of connection till
+    /// connection is closed
+    ///
+    /// # Errors
+    /// If export cannot be completed
+    async fn export<T>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        target_migrator: &T,
+    ) -> Result<ExportReport, Error>
+    where
+        T: TargetMigrator<DB> + ?Sized,
+    {
+        tracing::debug!("exporting to target migrator");
+        let mut guard = LockGuard::acquire(self, BorrowedConnection(connection), None).await?;
+        let connection = &mut *guard;
+        // Result is returned after unlocking so lock is released even on failure
+        // or panic
+        let result = catch_unwind(async {
+            target_migrator.lock_target(connection).await?;
+            let result = catch_unwind(export_to_target(self, connection, target_migrator)).await;
+            // unlock target migrator before returning result or resuming panic
+            let unlock_result = target_migrator.unlock_target(connection).await;
+            match (result, unlock_result) {
+                (Ok(Ok(report)), Ok(())) => Ok(report),
+                (Ok(Ok(_)), Err(unlock_error)) => Err(unlock_error),
+                (Ok(Err(error)), Ok(())) => Err(error),
+                (Ok(Err(error)), Err(unlock_error)) => {
+                    Err(Error::LockReleaseFailed {
+                        error: Box::new(error),
+                        unlock_error: Box::new(unlock_error),
+                    })
+                }
+                (Err(payload), unlock_result) => {
+                    if let Err(unlock_error) = unlock_result {
+                        tracing::error!(
+                            "failed to unlock target migrator after panic: {unlock_error}"
+                        );
+                    }
+                    std::panic::resume_unwind(payload)
+                }
+            }
+        })
+        .await;
+        guard.release_unwind(result).await
+    }
+}
+
+/// Export applied migrations of migrator to target migrator while both of them
+/// are locked
+async fn export_to_target<DB, M, T>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    target_migrator: &T,
+) -> Result<ExportReport, Error>
+where
+    DB: Database,
+    M: Info<DB> + DatabaseOperation<DB> + ?Sized,
+    T: TargetMigrator<DB> + ?Sized,
+{
+    target_migrator
+        .ensure_target_table_exists(connection)
+        .await?;
+    let applied_migrations = migrator.fetch_applied_migration_from_db(connection).await?;
+    let full_migration_list = migrator.migrations();
+    let mut report = ExportReport::default();
+    // Migration present in full migration list is exported so its checksum
+    // is used by target migrator
+    let virtual_migrations = applied_migrations
+        .iter()
+        .map(|applied_migration| {
+            Box::new((
+                applied_migration.app().to_string(),
+                applied_migration.name().to_string(),
+            )) as Box<dyn Migration<DB>>
+        })
+        .collect::<Vec<_>>();
+    let mut export_migrations = Vec::with_capacity(applied_migrations.len());
+    for (applied_migration, virtual_migration) in applied_migrations.iter().zip(&virtual_migrations)
+    {
+        // partially applied migration is not completely applied so it is not
+        // exported
+        if applied_migration.is_partially_applied() {
+            report.skipped.push((
+                applied_migration.app().to_string(),
+                applied_migration.name().to_string(),
+            ));
+            continue;
+        }
+        let migration = full_migration_list
+            .iter()
+            .find(|full_migration| *full_migration == virtual_migration)
+            .unwrap_or(virtual_migration);
+        export_migrations.push((migration, applied_migration));
+    }
+    // migrations are added inside single transaction so that export is
+    // either completed or not done at all
+    let mut transaction = connection.begin().await?;
+    let statuses = target_migrator
+        .add_applied_migrations(&mut transaction, &export_migrations)
+        .await?;
+    // status is reported for each migration so mismatch means some migrations
+    // status is unknown, transaction is dropped without commit in such case
+    if statuses.len() != export_migrations.len() {
+        for (migration, _) in export_migrations.iter().skip(statuses.len()) {
+            tracing::error!(
+                "target migrator did not return export status for migration {} : {}",
+                migration.app(),
+                migration.name()
+            );
+        }
+        return Err(Error::ExportStatusMismatch {
+            migrations: export_migrations.len(),
+            statuses: statuses.len(),
+        });
+    }
+    transaction.commit().await?;
+    for ((migration, _), status) in export_migrations.iter().zip(statuses) {
+        let app_name = (migration.app().to_string(), migration.name().to_string());
+        match status {
+            ExportStatus::Exported => report.exported.push(app_name),
+            ExportStatus::AlreadyExported => report.already_exported.push(app_name),
+            ExportStatus::Skipped => report.skipped.push(app_name),
+        }
+    }
+    Ok(report)
+}
+
+impl<DB> Export<DB> for Migrator<DB>
+where
+    DB: Database,
+    Self: DatabaseOperation<DB>,
+{
+}
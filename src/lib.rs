@@ -5,12 +5,26 @@
 //! Check `README.MD` for more detailed information of how to use a crate
 //! and visit [`Operation`], [`Migration`] and [`Migrator`]
 
+// tests which uses tokio are only present when sqlite feature is enabled
+#[cfg(all(test, not(feature = "sqlite")))]
+use tokio as _;
+
+// allow macros which refer items using crate name to be used in tests
+#[cfg(all(test, feature = "macros"))]
+extern crate self as sqlx_migrator;
+
+#[cfg(feature = "macros")]
+#[doc(inline)]
+pub use sqlx_migrator_macros::embed_migrations;
+
 #[cfg(feature = "cli")]
 #[doc(inline)]
 pub use crate::cli::MigrationCommand;
 #[doc(inline)]
 pub use crate::error::Error;
 #[doc(inline)]
+pub use crate::export::{Export, TargetMigrator};
+#[doc(inline)]
 pub use crate::migration::Migration;
 #[doc(inline)]
 pub use crate::migrator::{Info, Migrate, Migrator, Plan};
@@ -22,8 +36,11 @@ pub use crate::sync::{OldMigrator, Synchronize};
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod error;
+pub mod export;
 mod macros;
 pub mod migration;
 pub mod migrator;
 pub mod operation;
+pub mod sql;
 pub mod sync;
+mod timer;
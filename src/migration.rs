@@ -52,8 +52,37 @@ impl Migration<Sqlite> for ExampleMigration {
 ```
 "#
 )]
+#![cfg_attr(
+    all(feature = "sqlite", feature = "macros"),
+    doc = r#"
+Migration trait can also be derived when `macros` feature is enabled
+
+### Example
+```rust,no_run
+use sqlx_migrator::migration::Migration;
+use sqlx::Sqlite;
+
+#[derive(Migration)]
+#[migration(
+    db = Sqlite,
+    app = "example",
+    name = "second_migration",
+    parents(("example", "first_migration")),
+    operations(("CREATE TABLE sample (id INTEGER)", "DROP TABLE sample")),
+    atomic = false
+)]
+struct SecondMigration;
+```
+"#
+)]
 
 use std::hash::Hash;
+use std::time::Duration;
+
+use sqlx::Database;
+#[cfg(feature = "macros")]
+#[doc(inline)]
+pub use sqlx_migrator_macros::Migration;
 
 use crate::operation::Operation;
 
@@ -145,6 +174,45 @@ pub trait Migration<DB>: Send + Sync {
     fn is_virtual(&self) -> bool {
         false
     }
+
+    /// Returns the timeout of each operation of the migration.
+    /// By default, this function returns `None`, meaning operations are run
+    /// without timeout.
+    ///
+    /// Timeout is used for operations which do not provide their own
+    /// [`Operation::timeout`]. If database cancels statement of an operation
+    /// which takes longer than timeout, the migration fails with
+    /// [`Error::OperationTimeout`](crate::error::Error::OperationTimeout)
+    /// otherwise operation is cancelled and the migration fails with
+    /// [`Error::OperationCancelled`](crate::error::Error::OperationCancelled).
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns the checksum of the migration.
+    ///
+    /// Checksum is stored when migration is applied and it is compared with
+    /// the current checksum during plan generation so any modification of
+    /// already applied migration can be detected. By default, checksum is
+    /// calculated from [`Operation::fingerprint`] of all operations and it is
+    /// `None` if any operation do not provide fingerprint.
+    ///
+    /// Checksum is not backfilled for migrations applied by older version of
+    /// migrator which did not store checksum. Such migrations have `NULL`
+    /// checksum in migration table and they are never checked for
+    /// modification, same as migrations whose checksum is `None`.
+    fn checksum(&self) -> Option<String>
+    where
+        DB: Database,
+    {
+        let mut hasher = crc32fast::Hasher::new();
+        for operation in self.operations() {
+            let fingerprint = operation.fingerprint()?;
+            hasher.update(&fingerprint.len().to_le_bytes());
+            hasher.update(fingerprint.as_bytes());
+        }
+        Some(format!("{:08x}", hasher.finalize()))
+    }
 }
 
 impl<DB> PartialEq for dyn Migration<DB> {
@@ -186,28 +254,52 @@ where
     fn is_virtual(&self) -> bool {
         true
     }
+
+    fn checksum(&self) -> Option<String>
+    where
+        DB: Database,
+    {
+        None
+    }
 }
 
 /// Struct representing a migration row from the database.
 ///
-/// This struct corresponds to the id, app, name, and applied time fields in the
-/// database. It is used to list the migrations that have been applied.
+/// This struct corresponds to the id, app, name, applied time, checksum,
+/// duration, applied by and applied operations fields in the database. It is
+/// used to list the migrations that have been applied.
 #[derive(sqlx::FromRow, Clone)]
 pub struct AppliedMigrationSqlRow {
     id: i32,
     app: String,
     name: String,
     applied_time: String,
+    #[sqlx(default)]
+    checksum: Option<String>,
+    #[sqlx(default)]
+    duration_ms: Option<i64>,
+    #[sqlx(default)]
+    applied_by: Option<String>,
+    #[sqlx(default)]
+    applied_operations: Option<i64>,
 }
 
 impl AppliedMigrationSqlRow {
     #[cfg(test)]
+    #[cfg_attr(
+        not(feature = "sqlite"),
+        expect(dead_code, reason = "only used by tests which require sqlite")
+    )]
     pub(crate) fn new(id: i32, app: &str, name: &str) -> Self {
         Self {
             id,
             app: app.to_string(),
             name: name.to_string(),
             applied_time: String::new(),
+            checksum: None,
+            duration_ms: None,
+            applied_by: None,
+            applied_operations: None,
         }
     }
 
@@ -234,6 +326,43 @@ impl AppliedMigrationSqlRow {
     pub fn applied_time(&self) -> &str {
         &self.applied_time
     }
+
+    /// Return checksum of migration stored at time of applying migration.
+    /// It is `None` for migration applied before checksum was stored
+    #[must_use]
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
+    }
+
+    /// Return time taken for applying migration. Migration which are applied
+    /// as fake do not have duration
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_ms
+            .and_then(|duration_ms| u64::try_from(duration_ms).ok())
+            .map(Duration::from_millis)
+    }
+
+    /// Return user and host which applied migration
+    #[must_use]
+    pub fn applied_by(&self) -> Option<&str> {
+        self.applied_by.as_deref()
+    }
+
+    /// Return number of operations applied if migration is partially applied.
+    /// Non atomic migration which failed in between its operations is left
+    /// partially applied
+    #[must_use]
+    pub fn applied_operations(&self) -> Option<usize> {
+        self.applied_operations
+            .and_then(|applied_operations| usize::try_from(applied_operations).ok())
+    }
+
+    /// Return true if migration is partially applied
+    #[must_use]
+    pub fn is_partially_applied(&self) -> bool {
+        self.applied_operations.is_some()
+    }
 }
 
 impl<DB> PartialEq<Box<dyn Migration<DB>>> for AppliedMigrationSqlRow {
@@ -241,3 +370,111 @@ impl<DB> PartialEq<Box<dyn Migration<DB>>> for AppliedMigrationSqlRow {
         self.app == other.app() && self.name == other.name()
     }
 }
+
+/// Enum representing the type of event stored in migration history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HistoryEvent {
+    /// Migration was applied
+    Apply,
+    /// Migration was reverted
+    Revert,
+    /// Migration was marked as applied without running its operations
+    FakeApply,
+    /// Migration was marked as reverted without running its operations
+    FakeRevert,
+    /// Migration was added by syncing from old migrator
+    Sync,
+    /// Migration was added by exporting from other migrator
+    Export,
+}
+
+impl HistoryEvent {
+    /// Returns the value which is stored in database for event
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryEvent::Apply => "apply",
+            HistoryEvent::Revert => "revert",
+            HistoryEvent::FakeApply => "fake_apply",
+            HistoryEvent::FakeRevert => "fake_revert",
+            HistoryEvent::Sync => "sync",
+            HistoryEvent::Export => "export",
+        }
+    }
+}
+
+/// Enum representing direction in which operations of migration are run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Direction {
+    /// Operations are applied using up function
+    Apply,
+    /// Operations are reverted using down function
+    Revert,
+}
+
+impl Direction {
+    /// Returns name of direction
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Apply => "apply",
+            Direction::Revert => "revert",
+        }
+    }
+}
+
+/// Struct representing a migration history row from the database.
+///
+/// This struct corresponds to the id, app, name, event, event time and
+/// performed by fields in the database. Unlike [`AppliedMigrationSqlRow`]
+/// history rows are never deleted, so they can be used to audit all events
+/// which happened to migrations.
+#[derive(sqlx::FromRow, Clone)]
+pub struct MigrationHistorySqlRow {
+    id: i32,
+    app: String,
+    name: String,
+    event: String,
+    event_time: String,
+    performed_by: Option<String>,
+}
+
+impl MigrationHistorySqlRow {
+    /// Return id value present on database
+    #[must_use]
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// Return migration app name stored inside database
+    #[must_use]
+    pub fn app(&self) -> &str {
+        &self.app
+    }
+
+    /// Return migration name stored inside database
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return event name. See [`HistoryEvent::as_str`] for possible values
+    #[must_use]
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+
+    /// Return time when event occurred
+    #[must_use]
+    pub fn event_time(&self) -> &str {
+        &self.event_time
+    }
+
+    /// Return user and host which performed event
+    #[must_use]
+    pub fn performed_by(&self) -> Option<&str> {
+        self.performed_by.as_deref()
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 #[cfg(feature = "mysql")]
 use sqlx::MySql;
 #[cfg(feature = "postgres")]
@@ -13,9 +15,9 @@ use super::mysql;
 use super::postgres;
 #[cfg(feature = "sqlite")]
 use super::sqlite;
-use super::{DatabaseOperation, Migrator};
+use super::{DatabaseOperation, LockHolder, Migrator, TransientError, add_missing_columns};
 use crate::error::Error;
-use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::migration::{AppliedMigrationSqlRow, HistoryEvent, Migration, MigrationHistorySqlRow};
 
 /// get database name
 async fn get_database_name(
@@ -46,18 +48,38 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         &self,
         connection: &mut <Any as Database>::Connection,
     ) -> Result<(), Error> {
-        let sql_query = match connection.backend_name() {
+        let table_name = self.table_name();
+        let history_table_name = self.history_table_name();
+        let (sql_query, upgrade_columns, history_sql_query) = match connection.backend_name() {
             #[cfg(feature = "postgres")]
             <Postgres as Database>::NAME => {
-                postgres::create_migrator_table_query(&self.table_name())
+                (
+                    postgres::create_migrator_table_query(&table_name),
+                    postgres::migrator_table_upgrade_columns(),
+                    postgres::create_history_table_query(&history_table_name),
+                )
             }
             #[cfg(feature = "sqlite")]
-            <Sqlite as Database>::NAME => sqlite::create_migrator_table_query(&self.table_name()),
+            <Sqlite as Database>::NAME => {
+                (
+                    sqlite::create_migrator_table_query(&table_name),
+                    sqlite::migrator_table_upgrade_columns(),
+                    sqlite::create_history_table_query(&history_table_name),
+                )
+            }
             #[cfg(feature = "mysql")]
-            <MySql as Database>::NAME => mysql::create_migrator_table_query(&self.table_name()),
+            <MySql as Database>::NAME => {
+                (
+                    mysql::create_migrator_table_query(&table_name),
+                    mysql::migrator_table_upgrade_columns(),
+                    mysql::create_history_table_query(&history_table_name),
+                )
+            }
             _ => return Err(Error::UnsupportedDatabase),
         };
-        sqlx::query(&sql_query).execute(connection).await?;
+        sqlx::query(&sql_query).execute(&mut *connection).await?;
+        add_missing_columns::<Any>(&mut *connection, &table_name, upgrade_columns).await?;
+        sqlx::query(&history_sql_query).execute(connection).await?;
         Ok(())
     }
 
@@ -65,13 +87,22 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         &self,
         connection: &mut <Any as Database>::Connection,
     ) -> Result<(), Error> {
+        let table_name = self.table_name();
         let sql_query = match connection.backend_name() {
             #[cfg(feature = "postgres")]
-            <Postgres as Database>::NAME => postgres::drop_table_query(&self.table_name()),
+            <Postgres as Database>::NAME => postgres::drop_table_query(&table_name),
             #[cfg(feature = "sqlite")]
-            <Sqlite as Database>::NAME => sqlite::drop_table_query(&self.table_name()),
+            <Sqlite as Database>::NAME => {
+                return sqlite::drop_migration_tables::<Any>(
+                    connection,
+                    &table_name,
+                    &sqlite::lock_owner(self),
+                    self.lock_expiry(),
+                )
+                .await;
+            }
             #[cfg(feature = "mysql")]
-            <MySql as Database>::NAME => mysql::drop_table_query(&self.table_name()),
+            <MySql as Database>::NAME => mysql::drop_table_query(&table_name),
             _ => return Err(Error::UnsupportedDatabase),
         };
         sqlx::query(&sql_query).execute(connection).await?;
@@ -93,6 +124,64 @@ impl DatabaseOperation<Any> for Migrator<Any> {
             _ => return Err(Error::UnsupportedDatabase),
         };
         sqlx::query(&sql_query)
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table_with_duration(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        migration: &Box<dyn Migration<Any>>,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                postgres::add_migration_with_duration_query(&self.table_name())
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                sqlite::add_migration_with_duration_query(&self.table_name())
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                mysql::add_migration_with_duration_query(&self.table_name())
+            }
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        sqlx::query(&sql_query)
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_duration(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        migration: &Box<dyn Migration<Any>>,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => postgres::update_duration_query(&self.table_name()),
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => sqlite::update_duration_query(&self.table_name()),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::update_duration_query(&self.table_name()),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        sqlx::query(&sql_query)
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
             .bind(migration.app())
             .bind(migration.name())
             .execute(connection)
@@ -100,6 +189,89 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         Ok(())
     }
 
+    async fn update_applied_operations(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        migration: &Box<dyn Migration<Any>>,
+        applied_operations: Option<usize>,
+    ) -> Result<(), Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                postgres::update_applied_operations_query(&self.table_name())
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                sqlite::update_applied_operations_query(&self.table_name())
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::update_applied_operations_query(&self.table_name()),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        sqlx::query(&sql_query)
+            .bind(applied_operations.map(|count| i64::try_from(count).unwrap_or(i64::MAX)))
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_checksum(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        migration: &Box<dyn Migration<Any>>,
+    ) -> Result<(), Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => postgres::update_checksum_query(&self.table_name()),
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => sqlite::update_checksum_query(&self.table_name()),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::update_checksum_query(&self.table_name()),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        sqlx::query(&sql_query)
+            .bind(migration.checksum())
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        migration: &Box<dyn Migration<Any>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                postgres::add_migration_with_applied_time_query(&self.table_name())
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                sqlite::add_migration_with_applied_time_query(&self.table_name())
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                mysql::add_migration_with_applied_time_query(&self.table_name())
+            }
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        sqlx::query(&sql_query)
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(applied_time)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
     async fn delete_migration_from_db_table(
         &self,
         connection: &mut <Any as Database>::Connection,
@@ -141,7 +313,170 @@ impl DatabaseOperation<Any> for Migrator<Any> {
             .await?)
     }
 
+    async fn add_history_to_db_table(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        migration: &Box<dyn Migration<Any>>,
+        event: HistoryEvent,
+    ) -> Result<(), Error> {
+        let sql_query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => postgres::add_history_query(&self.history_table_name()),
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => sqlite::add_history_query(&self.history_table_name()),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => mysql::add_history_query(&self.history_table_name()),
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        sqlx::query(&sql_query)
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(event.as_str())
+            .bind(self.applied_by())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_history_from_db(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+    ) -> Result<Vec<MigrationHistorySqlRow>, Error> {
+        let query = match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                postgres::fetch_history_rows_query(&self.history_table_name())
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                sqlite::fetch_history_rows_query(&self.history_table_name())
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                mysql::fetch_history_rows_query(&self.history_table_name())
+            }
+            _ => return Err(Error::UnsupportedDatabase),
+        };
+        Ok(sqlx::query_as::<_, MigrationHistorySqlRow>(&query)
+            .fetch_all(connection)
+            .await?)
+    }
+
+    async fn is_transactional_ddl_supported(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+    ) -> Result<bool, Error> {
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => Ok(true),
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => Ok(true),
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => Ok(false),
+            _ => Err(Error::UnsupportedDatabase),
+        }
+    }
+
+    fn is_operation_progress_supported(&self) -> bool {
+        true
+    }
+
+    async fn set_operation_timeout(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<Vec<(String, String)>, Error> {
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                let settings = postgres::operation_timeout_settings(timeout);
+                postgres::replace_settings::<Any>(connection, settings).await
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                let settings = sqlite::operation_timeout_settings(timeout);
+                sqlite::replace_settings::<Any>(connection, settings).await
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                let settings = mysql::operation_timeout_settings(timeout);
+                mysql::replace_settings::<Any>(connection, settings).await
+            }
+            _ => Err(Error::UnsupportedDatabase),
+        }
+    }
+
+    async fn restore_operation_timeout(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        settings: Vec<(String, String)>,
+    ) -> Result<(), Error> {
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                postgres::replace_settings::<Any>(connection, settings).await?;
+            }
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                sqlite::replace_settings::<Any>(connection, settings).await?;
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                mysql::replace_settings::<Any>(connection, settings).await?;
+            }
+            _ => return Err(Error::UnsupportedDatabase),
+        }
+        Ok(())
+    }
+
+    fn is_timeout_error(&self, error: &Error) -> bool {
+        // error of any connection is error of its underlying database so each
+        // supported database is checked
+        #[cfg(feature = "postgres")]
+        if postgres::is_timeout_error(error) {
+            return true;
+        }
+        #[cfg(feature = "sqlite")]
+        if sqlite::is_timeout_error(error) {
+            return true;
+        }
+        #[cfg(feature = "mysql")]
+        if mysql::is_timeout_error(error) {
+            return true;
+        }
+        false
+    }
+
+    fn transient_error(&self, error: &Error) -> Option<TransientError> {
+        // error of any connection is error of its underlying database so each
+        // supported database is checked
+        #[cfg(feature = "postgres")]
+        if let Some(transient_error) = postgres::transient_error(error) {
+            return Some(transient_error);
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(transient_error) = sqlite::transient_error(error) {
+            return Some(transient_error);
+        }
+        #[cfg(feature = "mysql")]
+        if let Some(transient_error) = mysql::transient_error(error) {
+            return Some(transient_error);
+        }
+        None
+    }
+
     async fn lock(&self, connection: &mut <Any as Database>::Connection) -> Result<(), Error> {
+        #[cfg(feature = "sqlite")]
+        if connection.backend_name() == <Sqlite as Database>::NAME {
+            return sqlite::acquire_lock::<Any>(
+                connection,
+                &self.table_name(),
+                sqlite::lock_owner(self),
+                self.lock_expiry(),
+                None,
+            )
+            .await;
+        }
         let database_name = get_database_name(connection).await?;
         if let Some(name) = database_name {
             let mut arguments = AnyArguments::default();
@@ -151,8 +486,6 @@ impl DatabaseOperation<Any> for Migrator<Any> {
                     arguments.add(postgres::get_lock_id(&name, &self.table_name()))?;
                     postgres::lock_database_query()
                 }
-                #[cfg(feature = "sqlite")]
-                <Sqlite as Database>::NAME => return Ok(()),
                 #[cfg(feature = "mysql")]
                 <MySql as Database>::NAME => {
                     arguments.add(mysql::get_lock_id(&name, &self.table_name()))?;
@@ -167,7 +500,81 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         Ok(())
     }
 
+    async fn lock_with_timeout(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        #[cfg(feature = "sqlite")]
+        if connection.backend_name() == <Sqlite as Database>::NAME {
+            return sqlite::acquire_lock::<Any>(
+                connection,
+                &self.table_name(),
+                sqlite::lock_owner(self),
+                self.lock_expiry(),
+                Some(timeout),
+            )
+            .await;
+        }
+        let Some(name) = get_database_name(connection).await? else {
+            return Ok(());
+        };
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                postgres::try_lock_with_timeout::<Any>(
+                    connection,
+                    postgres::get_lock_id(&name, &self.table_name()),
+                    timeout,
+                )
+                .await
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                let (locked,): (Option<i64>,) =
+                    sqlx::query_as(mysql::lock_database_with_timeout_query())
+                        .bind(mysql::get_lock_id(&name, &self.table_name()))
+                        .bind(mysql::lock_timeout_secs(timeout))
+                        .fetch_one(connection)
+                        .await?;
+                if locked != Some(1) {
+                    return Err(Error::LockTimeout { timeout });
+                }
+                Ok(())
+            }
+            _ => Err(Error::UnsupportedDatabase),
+        }
+    }
+
+    async fn refresh_lock(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+    ) -> Result<(), Error> {
+        match connection.backend_name() {
+            #[cfg(feature = "sqlite")]
+            <Sqlite as Database>::NAME => {
+                sqlite::refresh_lock::<Any>(
+                    connection,
+                    &self.table_name(),
+                    sqlite::lock_owner(self),
+                )
+                .await
+            }
+            // advisory lock is held till session is ended
+            _ => Ok(()),
+        }
+    }
+
     async fn unlock(&self, connection: &mut <Any as Database>::Connection) -> Result<(), Error> {
+        #[cfg(feature = "sqlite")]
+        if connection.backend_name() == <Sqlite as Database>::NAME {
+            return sqlite::release_lock::<Any>(
+                connection,
+                &self.table_name(),
+                sqlite::lock_owner(self),
+            )
+            .await;
+        }
         let database_name = get_database_name(connection).await?;
         if let Some(name) = database_name {
             let mut arguments = AnyArguments::default();
@@ -177,8 +584,6 @@ impl DatabaseOperation<Any> for Migrator<Any> {
                     arguments.add(postgres::get_lock_id(&name, &self.table_name()))?;
                     postgres::unlock_database_query()
                 }
-                #[cfg(feature = "sqlite")]
-                <Sqlite as Database>::NAME => return Ok(()),
                 #[cfg(feature = "mysql")]
                 <MySql as Database>::NAME => {
                     arguments.add(mysql::get_lock_id(&name, &self.table_name()))?;
@@ -192,4 +597,58 @@ impl DatabaseOperation<Any> for Migrator<Any> {
         }
         Ok(())
     }
+
+    async fn fetch_lock_holder(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+    ) -> Result<Option<LockHolder>, Error> {
+        #[cfg(feature = "sqlite")]
+        if connection.backend_name() == <Sqlite as Database>::NAME {
+            return sqlite::fetch_lock_holder::<Any>(connection, &self.table_name()).await;
+        }
+        let Some(name) = get_database_name(connection).await? else {
+            return Ok(None);
+        };
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                let lock_id = postgres::get_lock_id(&name, &self.table_name());
+                postgres::fetch_lock_holder::<Any>(connection, lock_id).await
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                let lock_id = mysql::get_lock_id(&name, &self.table_name());
+                mysql::fetch_lock_holder::<Any>(connection, lock_id).await
+            }
+            _ => Err(Error::UnsupportedDatabase),
+        }
+    }
+
+    async fn force_unlock(
+        &self,
+        connection: &mut <Any as Database>::Connection,
+        session: &str,
+    ) -> Result<bool, Error> {
+        #[cfg(feature = "sqlite")]
+        if connection.backend_name() == <Sqlite as Database>::NAME {
+            return sqlite::force_release_lock::<Any>(connection, &self.table_name(), session)
+                .await;
+        }
+        let Some(name) = get_database_name(connection).await? else {
+            return Ok(false);
+        };
+        match connection.backend_name() {
+            #[cfg(feature = "postgres")]
+            <Postgres as Database>::NAME => {
+                let lock_id = postgres::get_lock_id(&name, &self.table_name());
+                postgres::force_unlock::<Any>(connection, lock_id, session).await
+            }
+            #[cfg(feature = "mysql")]
+            <MySql as Database>::NAME => {
+                let lock_id = mysql::get_lock_id(&name, &self.table_name());
+                mysql::force_unlock::<Any>(connection, lock_id, session).await
+            }
+            _ => Err(Error::UnsupportedDatabase),
+        }
+    }
 }
@@ -0,0 +1,2 @@
+-- migrator:no-transaction
+CREATE INDEX user_id ON user (id);
@@ -0,0 +1 @@
+CREATE TABLE comment (id INTEGER);
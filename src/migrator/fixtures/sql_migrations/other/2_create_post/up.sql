@@ -0,0 +1,3 @@
+-- migrator:parent main/0001_create_user
+-- migrator:destructible
+CREATE TABLE post (id INTEGER);
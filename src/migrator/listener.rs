@@ -0,0 +1,118 @@
+use sqlx::Database;
+
+use super::RunReport;
+use crate::error::Error;
+use crate::migration::{Direction, Migration};
+
+/// Listener which is notified about progress of plan run by
+/// [`Migrate::run`](super::Migrate::run)
+///
+/// Listener can be used for sending notification, emitting metrics or
+/// refreshing caches around migrations. All methods have default
+/// implementation which do nothing so only required callbacks need to be
+/// implemented. Error returned by callback fails the run in same way as error
+/// of migration except error of [`MigrationListener::after_migration`] which is
+/// only logged. Callbacks are not called for dry run of plan.
+#[cfg_attr(
+    feature = "sqlite",
+    doc = r#"
+### Example
+```rust,no_run
+use sqlx::{Sqlite, SqliteConnection};
+use sqlx_migrator::error::Error;
+use sqlx_migrator::migration::{Direction, Migration};
+use sqlx_migrator::migrator::{MigrationListener, Migrator};
+
+struct LogListener;
+
+#[async_trait::async_trait]
+impl MigrationListener<Sqlite> for LogListener {
+    async fn after_migration(
+        &self,
+        _connection: &mut SqliteConnection,
+        migration: &dyn Migration<Sqlite>,
+        direction: Direction,
+    ) -> Result<(), Error> {
+        println!("{} {} : {}", direction.as_str(), migration.app(), migration.name());
+        Ok(())
+    }
+}
+
+let migrator = Migrator::<Sqlite>::default().add_listener(Box::new(LogListener));
+```
+"#
+)]
+#[async_trait::async_trait]
+pub trait MigrationListener<DB>: Send + Sync
+where
+    DB: Database,
+{
+    /// Called once plan is generated before any migration of plan is run.
+    /// Migrations are in order in which they are run
+    async fn before_plan(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _direction: Direction,
+        _migrations: &[&dyn Migration<DB>],
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called before migration is run. It is called again when migration is
+    /// retried
+    async fn before_migration(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &dyn Migration<DB>,
+        _direction: Direction,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called after operation of migration is run. Connection is transaction
+    /// of migration when migration is atomic
+    async fn after_operation(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &dyn Migration<DB>,
+        _direction: Direction,
+        _operation_index: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called after migration is run and its transaction is committed. It is
+    /// called once even if migration is retried. Since migration is already
+    /// committed, returned error is only logged and it do not fail migration
+    async fn after_migration(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &dyn Migration<DB>,
+        _direction: Direction,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called when migration fails. Transaction of atomic migration is already
+    /// rolled back when it is called
+    async fn on_error(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &dyn Migration<DB>,
+        _direction: Direction,
+        _error: &Error,
+    ) {
+    }
+
+    /// Called after all migrations of plan are run along with report of
+    /// migrations. It is not called when plan fails without continuing on
+    /// error
+    async fn after_plan(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _direction: Direction,
+        _report: &RunReport,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+use std::any::Any;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use sqlx::Database;
+use sqlx::pool::PoolConnection;
+
+use super::DatabaseOperation;
+use crate::error::Error;
+
+/// Connection owned by [`LockGuard`] on which migration lock is held
+///
+/// Guard owns connection so connection can be closed instead of being reused
+/// when guard is dropped without releasing lock. Trait is implemented for pool
+/// connection
+pub trait LockConnection<DB>: DerefMut<Target = <DB as Database>::Connection> + Send
+where
+    DB: Database,
+{
+    /// Discard connection so lock held by connection session is released when
+    /// connection is closed. It is called when guard is dropped without
+    /// releasing lock or when lock cannot be released. Lock stored in table,
+    /// such as lock of sqlite, is not removed by closing connection instead it
+    /// is taken over by other migrator once it expires
+    fn discard(&mut self);
+}
+
+impl<DB> LockConnection<DB> for PoolConnection<DB>
+where
+    DB: Database,
+{
+    fn discard(&mut self) {
+        self.close_on_drop();
+    }
+}
+
+/// Connection borrowed from caller which is used by methods taking borrowed
+/// connection. Borrowed connection cannot be closed so lock remains held by
+/// its session till caller closes it
+pub(crate) struct BorrowedConnection<'connection, DB>(
+    pub(crate) &'connection mut <DB as Database>::Connection,
+)
+where
+    DB: Database;
+
+impl<DB> Deref for BorrowedConnection<'_, DB>
+where
+    DB: Database,
+{
+    type Target = <DB as Database>::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<DB> DerefMut for BorrowedConnection<'_, DB>
+where
+    DB: Database,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+impl<DB> LockConnection<DB> for BorrowedConnection<'_, DB>
+where
+    DB: Database,
+{
+    fn discard(&mut self) {
+        tracing::error!(
+            "borrowed connection cannot be discarded, migration lock remains held till connection \
+             is closed"
+        );
+    }
+}
+
+/// Pool connection borrowed from caller which owns it. Discarded connection is
+/// closed instead of being returned to pool once caller drops it
+pub(crate) struct BorrowedPoolConnection<'connection, DB>(
+    pub(crate) &'connection mut PoolConnection<DB>,
+)
+where
+    DB: Database;
+
+impl<DB> Deref for BorrowedPoolConnection<'_, DB>
+where
+    DB: Database,
+{
+    type Target = <DB as Database>::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<DB> DerefMut for BorrowedPoolConnection<'_, DB>
+where
+    DB: Database,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+impl<DB> LockConnection<DB> for BorrowedPoolConnection<'_, DB>
+where
+    DB: Database,
+{
+    fn discard(&mut self) {
+        self.0.close_on_drop();
+    }
+}
+
+/// Guard which holds migration lock of migrator on connection
+///
+/// Guard can be used as connection while lock is held. Lock should be released
+/// using [`LockGuard::release`] or [`LockGuard::release_with`] since lock
+/// cannot be released while dropping guard. If guard is dropped without
+/// releasing lock, for example when future holding guard is cancelled,
+/// connection is discarded using [`LockConnection::discard`] instead of being
+/// reused so lock is released once connection is closed
+pub struct LockGuard<'migrator, DB, M, C>
+where
+    DB: Database,
+    M: DatabaseOperation<DB> + ?Sized,
+    C: LockConnection<DB>,
+{
+    migrator: &'migrator M,
+    connection: C,
+    locked: bool,
+    database: PhantomData<fn() -> DB>,
+}
+
+impl<'migrator, DB, M, C> LockGuard<'migrator, DB, M, C>
+where
+    DB: Database,
+    M: DatabaseOperation<DB> + Sync + ?Sized,
+    C: LockConnection<DB>,
+{
+    /// Acquire migration lock of migrator on connection. If timeout is
+    /// provided [`DatabaseOperation::lock_with_timeout`] is used for acquiring
+    /// lock
+    ///
+    /// # Errors
+    /// If lock cannot be acquired
+    pub async fn acquire(
+        migrator: &'migrator M,
+        mut connection: C,
+        timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
+        match timeout {
+            Some(timeout) => migrator.lock_with_timeout(&mut connection, timeout).await?,
+            None => migrator.lock(&mut connection).await?,
+        }
+        Ok(Self {
+            migrator,
+            connection,
+            locked: true,
+            database: PhantomData,
+        })
+    }
+
+    /// Release migration lock
+    ///
+    /// # Errors
+    /// If lock cannot be released. Connection is discarded in such case
+    pub async fn release(mut self) -> Result<(), Error> {
+        self.locked = false;
+        let result = self.migrator.unlock(&mut self.connection).await;
+        if result.is_err() {
+            self.connection.discard();
+        }
+        result
+    }
+
+    /// Release migration lock and return result of work done while holding
+    /// lock. If work is failed with [`Error::OperationCancelled`] connection is
+    /// discarded instead of releasing lock
+    ///
+    /// # Errors
+    /// If result is error or lock cannot be released. When both fails
+    /// [`Error::LockReleaseFailed`] containing both error is returned
+    pub async fn release_with<T>(mut self, result: Result<T, Error>) -> Result<T, Error> {
+        if let Err(error) = &result
+            && error.is_connection_discarded()
+        {
+            self.locked = false;
+            self.connection.discard();
+            return result;
+        }
+        match (result, self.release().await) {
+            (Ok(value), Ok(())) => Ok(value),
+            (Ok(_), Err(unlock_error)) => Err(unlock_error),
+            (Err(error), Ok(())) => Err(error),
+            (Err(error), Err(unlock_error)) => {
+                Err(Error::LockReleaseFailed {
+                    error: Box::new(error),
+                    unlock_error: Box::new(unlock_error),
+                })
+            }
+        }
+    }
+
+    /// Release migration lock after work done while holding lock is completed
+    /// or panicked. Panic is resumed after lock is released
+    pub(crate) async fn release_unwind<T>(
+        self,
+        result: Result<Result<T, Error>, Box<dyn Any + Send>>,
+    ) -> Result<T, Error> {
+        match result {
+            Ok(result) => self.release_with(result).await,
+            Err(payload) => {
+                if let Err(unlock_error) = self.release().await {
+                    tracing::error!("failed to release migration lock after panic: {unlock_error}");
+                }
+                std::panic::resume_unwind(payload)
+            }
+        }
+    }
+}
+
+impl<DB, M, C> Deref for LockGuard<'_, DB, M, C>
+where
+    DB: Database,
+    M: DatabaseOperation<DB> + ?Sized,
+    C: LockConnection<DB>,
+{
+    type Target = <DB as Database>::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.connection
+    }
+}
+
+impl<DB, M, C> DerefMut for LockGuard<'_, DB, M, C>
+where
+    DB: Database,
+    M: DatabaseOperation<DB> + ?Sized,
+    C: LockConnection<DB>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.connection
+    }
+}
+
+impl<DB, M, C> Drop for LockGuard<'_, DB, M, C>
+where
+    DB: Database,
+    M: DatabaseOperation<DB> + ?Sized,
+    C: LockConnection<DB>,
+{
+    fn drop(&mut self) {
+        if self.locked {
+            tracing::warn!("migration lock guard dropped without releasing lock");
+            self.connection.discard();
+        }
+    }
+}
+
+/// Session which holds migration lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockHolder {
+    session: String,
+    since: Option<String>,
+}
+
+impl LockHolder {
+    /// Create new lock holder from session identifier and time since when
+    /// lock is held
+    #[must_use]
+    pub fn new(session: String, since: Option<String>) -> Self {
+        Self { session, since }
+    }
+
+    /// Identifier of session which holds lock. For postgres it is backend
+    /// process id, for mysql it is connection id and for sqlite it is owner
+    /// stored in lock table
+    #[must_use]
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Time since when lock is held if it is known. Postgres and mysql do not
+    /// record time when lock is acquired so postgres uses start time of
+    /// holding session while mysql uses time since when holding session is in
+    /// its current state as reported by processlist
+    #[must_use]
+    pub fn since(&self) -> Option<&str> {
+        self.since.as_deref()
+    }
+}
+
+/// Future which catches panic raised while polling inner future
+pub(crate) struct CatchUnwind<F> {
+    future: Pin<Box<F>>,
+}
+
+impl<F> Future for CatchUnwind<F>
+where
+    F: Future,
+{
+    type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match std::panic::catch_unwind(AssertUnwindSafe(|| self.future.as_mut().poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Catch panic raised by future so that lock can be released before panic is
+/// resumed
+pub(crate) fn catch_unwind<F>(future: F) -> CatchUnwind<F>
+where
+    F: Future,
+{
+    CatchUnwind {
+        future: Box::pin(future),
+    }
+}
@@ -139,11 +139,21 @@ impl Synchronize<Postgres> for CustomMigrator {}
 )]
 
 use std::collections::HashMap;
+#[cfg(feature = "sqlite")]
+use std::hash::{BuildHasher as _, Hasher as _, RandomState};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-use sqlx::{Connection as _, Database};
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+use sqlx::{Column as _, Executor, IntoArguments, Statement as _};
+use sqlx::{Connection as _, Database, Pool};
+use tracing::Instrument as _;
 
 use crate::error::Error;
-use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::migration::{
+    AppliedMigrationSqlRow, Direction, HistoryEvent, Migration, MigrationHistorySqlRow,
+};
+use crate::operation::Operation;
 
 /// Any database module which support mysql, sqlite and postgres by default
 #[cfg(all(
@@ -152,10 +162,22 @@ use crate::migration::{AppliedMigrationSqlRow, Migration};
 ))]
 mod any;
 
+/// Module for lock guard
+mod lock;
+
+/// Module for migration listener
+mod listener;
+
 /// Module for mysql
 #[cfg(feature = "mysql")]
 mod mysql;
 
+/// Module for recording statements executed during dry run
+mod recorder;
+
+/// Module for retrying migration
+mod retry;
+
 /// Module for sqlite
 #[cfg(feature = "sqlite")]
 mod sqlite;
@@ -169,7 +191,13 @@ mod postgres;
 #[cfg(test)]
 mod tests;
 
-type BoxMigration<DB> = Box<dyn Migration<DB>>;
+pub use self::listener::MigrationListener;
+pub(crate) use self::lock::{BorrowedConnection, BorrowedPoolConnection, catch_unwind};
+pub use self::lock::{LockConnection, LockGuard, LockHolder};
+use self::recorder::StatementRecorder;
+pub use self::retry::{RetryPolicy, TransientError};
+
+pub(crate) type BoxMigration<DB> = Box<dyn Migration<DB>>;
 type MigrationVec<'migration, DB> = Vec<&'migration BoxMigration<DB>>;
 type MigrationVecResult<'migration, DB> = Result<MigrationVec<'migration, DB>, Error>;
 
@@ -184,6 +212,7 @@ enum PlanType {
 /// A [`Plan`] can specify whether to apply or revert migrations, and may target
 /// all migrations, specific migrations, or a limited number of migrations.
 #[derive(Debug)]
+#[expect(clippy::struct_excessive_bools)]
 pub struct Plan {
     #[expect(
         clippy::struct_field_names,
@@ -193,6 +222,10 @@ pub struct Plan {
     app_migration: Option<(String, Option<String>)>,
     count: Option<usize>,
     fake: bool,
+    lock_timeout: Option<Duration>,
+    continue_on_error: bool,
+    single_transaction: bool,
+    accept_checksum: bool,
 }
 
 impl Plan {
@@ -206,6 +239,10 @@ impl Plan {
             app_migration,
             count,
             fake: false,
+            lock_timeout: None,
+            continue_on_error: false,
+            single_transaction: false,
+            accept_checksum: false,
         }
     }
 
@@ -226,6 +263,71 @@ impl Plan {
         plan
     }
 
+    /// Sets timeout for acquiring migration lock while running plan. It
+    /// overrides timeout returned by [`Migrate::lock_timeout`] of migrator
+    /// while `None` uses timeout of migrator
+    ///
+    /// When timeout is set [`DatabaseOperation::lock_with_timeout`] is used
+    /// for acquiring lock instead of [`DatabaseOperation::lock`] so running
+    /// plan fails with [`Error::LockTimeout`] instead of waiting forever for
+    /// lock held by other migrator
+    #[must_use]
+    pub fn lock_timeout(self, lock_timeout: Option<Duration>) -> Self {
+        let mut plan = self;
+        plan.lock_timeout = lock_timeout;
+        plan
+    }
+
+    /// Sets whether plan continues running other migrations when a migration
+    /// fails.
+    ///
+    /// When enabled, migrations which depend upon failed migration through
+    /// parents or run before are skipped while unrelated migrations are still
+    /// run. While reverting, migrations upon which failed migration depends
+    /// are skipped instead. Result of each migration is returned by
+    /// [`Migrate::run_with_report`] while [`Migrate::run`] returns
+    /// [`Error::MigrationsFailed`] if any migration failed.
+    ///
+    /// By default, running plan stops at first failed migration
+    #[must_use]
+    pub fn continue_on_error(self, continue_on_error: bool) -> Self {
+        let mut plan = self;
+        plan.continue_on_error = continue_on_error;
+        plan
+    }
+
+    /// Sets whether all migrations of plan are run inside single transaction.
+    ///
+    /// When enabled, either all migrations of plan are committed or none of
+    /// them are. Each atomic migration uses savepoint inside transaction of
+    /// plan. Running plan fails with [`Error::PlanError`] if database do not
+    /// support transactional DDL, plan contains non atomic migration or plan
+    /// continues on error.
+    ///
+    /// By default, each atomic migration is run inside its own transaction
+    #[must_use]
+    pub fn single_transaction(self, single_transaction: bool) -> Self {
+        let mut plan = self;
+        plan.single_transaction = single_transaction;
+        plan
+    }
+
+    /// Sets whether changed checksum of applied migrations is accepted.
+    ///
+    /// Applying migrations fails with [`Error::ChecksumMismatch`] if applied
+    /// migration is modified after it was applied. When enabled, plan is
+    /// generated irrespective of checksum mismatch and stored checksum of
+    /// such migration is updated to its current checksum when plan is run.
+    /// Checksum is not compared for revert and fake plan.
+    ///
+    /// By default, changed checksum is not accepted
+    #[must_use]
+    pub fn accept_checksum(self, accept_checksum: bool) -> Self {
+        let mut plan = self;
+        plan.accept_checksum = accept_checksum;
+        plan
+    }
+
     /// Creates a new plan to apply all migrations.
     #[must_use]
     pub fn apply_all() -> Self {
@@ -267,6 +369,101 @@ impl Plan {
     pub fn revert_count(count: usize) -> Self {
         Self::new(PlanType::Revert, None, Some(count))
     }
+
+    /// Direction in which migrations of plan are run
+    fn direction(&self) -> Direction {
+        match self.plan_type {
+            PlanType::Apply => Direction::Apply,
+            PlanType::Revert => Direction::Revert,
+        }
+    }
+}
+
+/// Struct containing result of dry run of a migration
+///
+/// It contains statements which are executed by operations of migration in
+/// order of their execution. If operation is executed, statements logged by
+/// sqlx while it runs are recorded. Statements are not logged by sqlite since
+/// it runs them in its own worker thread and are not logged by any database
+/// if statement logging is disabled in connect options. In such case or when
+/// operation is not executed, statement is taken from
+/// [`Operation::up_sql`] or [`Operation::down_sql`]. If operation is not
+/// executed and do not expose its statement, statement is `None` whereas if
+/// executed operation do not expose its statement dry run fails with
+/// [`Error::DryRunStatementsNotCaptured`] instead of reporting no statement.
+#[derive(Debug, Clone)]
+pub struct DryRunMigration {
+    app: String,
+    name: String,
+    executed: bool,
+    statements: Vec<Option<String>>,
+}
+
+impl DryRunMigration {
+    /// Return app name of migration
+    #[must_use]
+    pub fn app(&self) -> &str {
+        &self.app
+    }
+
+    /// Return name of migration
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return true if operations of migration were executed inside a
+    /// transaction which was rolled back
+    #[must_use]
+    pub fn executed(&self) -> bool {
+        self.executed
+    }
+
+    /// Return statements of migration operations in order of execution
+    #[must_use]
+    pub fn statements(&self) -> &[Option<String>] {
+        &self.statements
+    }
+}
+
+/// Struct containing report of running plan
+///
+/// It contains app and name of migrations which succeeded, failed along with
+/// their error and skipped since migration which they depend upon failed.
+/// Migrations are only reported as failed or skipped when plan continues on
+/// error
+#[derive(Debug, Default)]
+pub struct RunReport {
+    succeeded: Vec<(String, String)>,
+    failed: Vec<(String, String, Error)>,
+    skipped: Vec<(String, String)>,
+}
+
+impl RunReport {
+    /// Returns app and name of migrations which are run successfully
+    #[must_use]
+    pub fn succeeded(&self) -> &[(String, String)] {
+        &self.succeeded
+    }
+
+    /// Returns app, name and error of migrations which failed
+    #[must_use]
+    pub fn failed(&self) -> &[(String, String, Error)] {
+        &self.failed
+    }
+
+    /// Returns app and name of migrations which are skipped since migration
+    /// which they depend upon failed or is skipped
+    #[must_use]
+    pub fn skipped(&self) -> &[(String, String)] {
+        &self.skipped
+    }
+
+    /// Returns true if no migration failed
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
 }
 
 /// The [`Info`] trait provides database-agnostic methods for managing
@@ -389,7 +586,8 @@ where
         connection: &mut <DB as Database>::Connection,
     ) -> Result<(), Error>;
 
-    /// Drop migration table if migration table exists
+    /// Drop migration table if migration table exists. History table is not
+    /// dropped so history of migrations is preserved
     async fn drop_migration_table_if_exists(
         &self,
         connection: &mut <DB as Database>::Connection,
@@ -402,6 +600,76 @@ where
         migration: &BoxMigration<DB>,
     ) -> Result<(), Error>;
 
+    /// Adds a migration record to the migration table in the database along
+    /// with time taken for applying migration.
+    ///
+    /// By default, duration is not stored and migration is added using
+    /// [`DatabaseOperation::add_migration_to_db_table`]
+    async fn add_migration_to_db_table_with_duration(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migration: &BoxMigration<DB>,
+        _duration: Duration,
+    ) -> Result<(), Error> {
+        self.add_migration_to_db_table(connection, migration).await
+    }
+
+    /// Updates time taken for applying a migration which is already added to
+    /// the migration table in the database. It is used for non atomic
+    /// migration which is added before running its operations
+    ///
+    /// By default, duration is not stored
+    async fn update_migration_duration(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &BoxMigration<DB>,
+        _duration: Duration,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Updates number of operations of non atomic migration which are applied.
+    /// `None` is used once all operations of migration are applied while
+    /// `Some` marks migration as partially applied so it can be resumed or
+    /// reverted from that operation
+    ///
+    /// By default, number of applied operations is not stored
+    async fn update_applied_operations(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &BoxMigration<DB>,
+        _applied_operations: Option<usize>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Updates stored checksum of applied migration to its current checksum.
+    /// It is used for accepting change of applied migration
+    ///
+    /// By default, checksum is not stored
+    async fn update_migration_checksum(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &BoxMigration<DB>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Adds a migration record to the migration table in the database with
+    /// provided applied time instead of current time.
+    ///
+    /// It is used to preserve applied time of migrations while syncing or
+    /// exporting. By default, applied time is not preserved and migration is
+    /// added using [`DatabaseOperation::add_migration_to_db_table`]
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        migration: &BoxMigration<DB>,
+        _applied_time: &str,
+    ) -> Result<(), Error> {
+        self.add_migration_to_db_table(connection, migration).await
+    }
+
     /// Removes a migration record from the migration table in the database.
     async fn delete_migration_from_db_table(
         &self,
@@ -416,11 +684,219 @@ where
         connection: &mut <DB as Database>::Connection,
     ) -> Result<Vec<AppliedMigrationSqlRow>, Error>;
 
+    /// Adds an event record of migration to the history table in the database.
+    ///
+    /// By default, history is not stored
+    async fn add_history_to_db_table(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _migration: &BoxMigration<DB>,
+        _event: HistoryEvent,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Fetches the list of migration events from the history table in the
+    /// database in order of their occurrence.
+    ///
+    /// By default, history is not stored so empty list is returned
+    async fn fetch_history_from_db(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<Vec<MigrationHistorySqlRow>, Error> {
+        Ok(vec![])
+    }
+
+    /// Returns true if database supports running DDL statements inside a
+    /// transaction which can be rolled back
+    ///
+    /// By default, transactional DDL is not supported
+    async fn is_transactional_ddl_supported(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// Returns true if migrator stores progress of non atomic migration using
+    /// [`DatabaseOperation::update_applied_operations`]. When supported,
+    /// migration is added to migration table before running its operations
+    /// so failure in between leaves migration as partially applied
+    ///
+    /// By default, progress is not stored and migration is only added once
+    /// all operations are applied
+    fn is_operation_progress_supported(&self) -> bool {
+        false
+    }
+
+    /// Set statement and lock wait timeout of connection to timeout of
+    /// operation which is going to be run. Returns name and previous value of
+    /// settings which are changed so they can be restored using
+    /// [`DatabaseOperation::restore_operation_timeout`]
+    ///
+    /// Postgres sets `statement_timeout` and `lock_timeout`, mysql sets
+    /// `max_execution_time` and `lock_wait_timeout` while sqlite sets
+    /// `busy_timeout`. By default, no setting is changed
+    async fn set_operation_timeout(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _timeout: Duration,
+    ) -> Result<Vec<(String, String)>, Error> {
+        Ok(vec![])
+    }
+
+    /// Restore settings of connection which are returned by
+    /// [`DatabaseOperation::set_operation_timeout`] once operation is
+    /// completed
+    ///
+    /// By default, settings are not restored
+    async fn restore_operation_timeout(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _settings: Vec<(String, String)>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns true if error is raised by database because statement or lock
+    /// wait exceeded timeout set by
+    /// [`DatabaseOperation::set_operation_timeout`]. Such error is reported as
+    /// [`Error::OperationTimeout`]
+    ///
+    /// By default, no error is considered as timeout error
+    fn is_timeout_error(&self, _error: &Error) -> bool {
+        false
+    }
+
+    /// Returns class of transient error if error is raised by database due to
+    /// concurrent transaction and can succeed when retried
+    ///
+    /// By default, no error is considered as transient error
+    fn transient_error(&self, _error: &Error) -> Option<TransientError> {
+        None
+    }
+
     /// Lock database while doing migrations so no two migrations run together
+    ///
+    /// Postgres and mysql use advisory lock while sqlite uses lock table which
+    /// contains row while lock is held so migrations of multiple process
+    /// using same database file are serialized
     async fn lock(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error>;
 
+    /// Lock database while doing migrations but only wait for lock till
+    /// timeout. If lock cannot be acquired within timeout
+    /// [`Error::LockTimeout`] is returned
+    ///
+    /// By default, timeout is ignored and lock is acquired using
+    /// [`DatabaseOperation::lock`]
+    async fn lock_with_timeout(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        _timeout: Duration,
+    ) -> Result<(), Error> {
+        self.lock(connection).await
+    }
+
+    /// Refresh lock held by connection. It is called before running each
+    /// migration of plan so lock which expires, such as lock of sqlite, is not
+    /// considered stale by other migrator
+    ///
+    /// By default, nothing is done since lock is held till it is released or
+    /// session of connection is ended
+    async fn refresh_lock(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// Unlock locked database
     async fn unlock(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error>;
+
+    /// Fetch session which currently holds migration lock. Returns `None` if
+    /// lock is not held
+    ///
+    /// By default, [`Error::LockIntrospectionUnsupported`] is returned
+    async fn fetch_lock_holder(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+    ) -> Result<Option<LockHolder>, Error> {
+        Err(Error::LockIntrospectionUnsupported)
+    }
+
+    /// Forcibly release migration lock if it is held by provided session,
+    /// which is [`LockHolder::session`] returned by
+    /// [`DatabaseOperation::fetch_lock_holder`]. Returns whether lock was held
+    /// by session and is released so lock acquired by other session after
+    /// holder was fetched is never released
+    ///
+    /// Advisory lock of postgres and mysql can only be released by session
+    /// which holds it so session holding lock is terminated when it is not
+    /// current session. By default, [`Error::LockIntrospectionUnsupported`]
+    /// is returned
+    async fn force_unlock(
+        &self,
+        _connection: &mut <DB as Database>::Connection,
+        _session: &str,
+    ) -> Result<bool, Error> {
+        Err(Error::LockIntrospectionUnsupported)
+    }
+
+    /// Acquire lock and return guard which releases lock. Guard owns
+    /// connection, such as pool connection, so connection can be discarded if
+    /// guard is dropped without releasing lock. Guard can be used as connection
+    /// while lock is held
+    ///
+    /// # Errors
+    /// If lock cannot be acquired
+    async fn acquire_lock<'migrator, C>(
+        &'migrator self,
+        connection: C,
+        timeout: Option<Duration>,
+    ) -> Result<LockGuard<'migrator, DB, Self, C>, Error>
+    where
+        Self: Sized,
+        C: LockConnection<DB> + 'migrator,
+    {
+        LockGuard::acquire(self, connection, timeout).await
+    }
+}
+
+/// Add missing columns to migrator table. Migrator table created by older
+/// version of library do not contain columns which were added later so such
+/// columns are added using provided list of column name and its definition
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub(crate) async fn add_missing_columns<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    columns: &[(&str, &str)],
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+{
+    let select_query = format!("SELECT * FROM {table_name}");
+    let existing_columns = (&mut *connection)
+        .prepare(&select_query)
+        .await?
+        .columns()
+        .iter()
+        .map(|column| column.name().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    for (column_name, column_definition) in columns {
+        if !existing_columns
+            .iter()
+            .any(|existing_column| existing_column == column_name)
+        {
+            sqlx::query(&format!(
+                "ALTER TABLE {table_name} ADD COLUMN {column_definition}"
+            ))
+            .execute(&mut *connection)
+            .await?;
+        }
+    }
+    Ok(())
 }
 
 /// populate replace hash map recursively
@@ -638,6 +1114,41 @@ where
     Ok(())
 }
 
+/// Returns true if migration depends upon any of blocked migration. While
+/// applying, migration depends upon its parents and migrations which run
+/// before it. While reverting, migration depends upon migrations which
+/// depend upon it since they need to be reverted first
+fn depends_on_blocked<DB>(
+    migration: &BoxMigration<DB>,
+    blocked: &[(String, String)],
+    migrations: &[BoxMigration<DB>],
+    plan_type: &PlanType,
+) -> bool
+where
+    DB: Database,
+{
+    let is_blocked = |other: &BoxMigration<DB>| {
+        blocked
+            .iter()
+            .any(|(app, name)| other.app() == app && other.name() == name)
+    };
+    match plan_type {
+        PlanType::Apply => {
+            migration.parents().iter().any(is_blocked)
+                || migrations
+                    .iter()
+                    .any(|other| other.run_before().contains(migration) && is_blocked(other))
+        }
+        PlanType::Revert => {
+            migrations.iter().any(|other| {
+                is_blocked(other)
+                    && (other.parents().contains(migration)
+                        || migration.run_before().contains(other))
+            })
+        }
+    }
+}
+
 // get all replaces migration recursively for a migration
 fn get_recursive_replaces<'get, DB>(
     hash_map: &'get HashMap<BoxMigration<DB>, &'get BoxMigration<DB>>,
@@ -650,365 +1161,1305 @@ fn get_recursive_replaces<'get, DB>(
     recursive_vec
 }
 
-/// The [`Migrate`] trait defines methods to manage and apply database
-/// migrations according to a given plan.
-///
-/// This trait combines the functionalities of the [`Info`] and
-/// [`DatabaseOperation`] traits, providing a full set of migration
-/// capabilities. All methods have default implementations, meaning no explicit
-/// implementation is required. Additionally, all methods are database-agnostic.
-#[async_trait::async_trait]
-pub trait Migrate<DB>: Info<DB> + DatabaseOperation<DB> + Send + Sync
-where
-    DB: Database,
-{
-    /// Generate migration plan according to plan.
-    ///
-    /// Returns a vector of migration. If plan is none than it will generate
-    /// plan with all migrations in order of apply
-    #[expect(clippy::too_many_lines)]
-    async fn generate_migration_plan(
-        &self,
-        connection: &mut <DB as Database>::Connection,
-        plan: Option<&Plan>,
-    ) -> MigrationVecResult<'_, DB> {
-        if self.migrations().is_empty() {
-            return Err(Error::PlanError {
-                message: "no migration are added to migration list".to_string(),
-            });
-        }
-        // if there is any virtual migration which is not replaced than return
-        // error since virtual migration should only be used for replacing
-        // another migration
-        if self
-            .migrations()
-            .iter()
-            .any(|migration| migration.is_virtual())
-        {
-            return Err(Error::PlanError {
-                message: "virtual migrations which is not replaced is present".to_string(),
-            });
+/// Returns function which wraps error raised by operation of migration in
+/// [`Error::OperationFailed`] so failing migration and operation can be
+/// identified
+fn operation_failed<DB>(
+    migration: &BoxMigration<DB>,
+    operation_index: usize,
+    direction: Direction,
+    rolled_back: bool,
+) -> impl FnOnce(Error) -> Error {
+    move |error| {
+        Error::OperationFailed {
+            app: migration.app().to_string(),
+            name: migration.name().to_string(),
+            operation_index,
+            direction,
+            rolled_back,
+            source: Box::new(error),
         }
+    }
+}
 
-        tracing::debug!("generating {:?} migration plan", plan);
+/// Record time taken and outcome of work done inside span
+fn record_span_outcome(span: &tracing::Span, start_time: Instant, outcome: &str) {
+    let duration_ms = u64::try_from(start_time.elapsed().as_millis()).unwrap_or(u64::MAX);
+    span.record("duration_ms", duration_ms);
+    span.record("outcome", outcome);
+}
 
-        // hashmap which contains key as child migration and value is parent
-        // migration which replaces this child migration. One migration can
-        // only have one parent
-        let mut replaces_child_parent_hash_map = HashMap::new();
+/// Returns outcome of result which is recorded in span
+fn result_outcome<T>(result: &Result<T, Error>) -> &'static str {
+    if result.is_ok() { "success" } else { "failure" }
+}
 
-        for parent_migration in self.migrations() {
-            for child_migration in parent_migration.replaces() {
-                let child_name = format!("{}:{}", child_migration.app(), child_migration.name());
-                if replaces_child_parent_hash_map
-                    .insert(child_migration, parent_migration)
-                    .is_some()
-                {
-                    return Err(Error::PlanError {
-                        message: format!("migration {child_name} replaced multiple times",),
-                    });
-                }
-            }
-        }
+/// Run up or down of operation inside span of operation
+async fn run_operation<DB, M>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    migration: &BoxMigration<DB>,
+    operation: &dyn Operation<DB>,
+    operation_index: usize,
+    direction: Direction,
+    recorder: Option<&StatementRecorder>,
+) -> Result<(), Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+{
+    let span = tracing::info_span!(
+        "operation",
+        app = migration.app(),
+        name = migration.name(),
+        direction = direction.as_str(),
+        operation_index,
+        duration_ms = tracing::field::Empty,
+        outcome = tracing::field::Empty,
+    );
+    let start_time = Instant::now();
+    let result = run_operation_with_timeout(
+        migrator, connection, migration, operation, direction, recorder,
+    )
+    .instrument(span.clone())
+    .await;
+    record_span_outcome(&span, start_time, result_outcome(&result));
+    result
+}
 
-        // Hashmap which contains key as migration and value is vector of migration
-        // which are children of this migration due to replace. One migration can
-        // have multiple children
-        let mut replace_children = HashMap::<_, Vec<_>>::new();
-        // in first loop add direct children of parent due to replace
-        for (child, &parent) in &replaces_child_parent_hash_map {
-            // if child is virtual than we need to find non virtual migration
-            let children_migration = if child.is_virtual() {
-                self.migrations()
-                    .iter()
-                    .find(|&search_migration| search_migration == child)
-                    .ok_or(Error::PlanError {
-                        message: "Failed finding non virtual migration for virtual migration"
-                            .to_string(),
-                    })?
-            } else {
-                child
-            };
-            replace_children
-                .entry(parent)
-                .or_default()
-                .push(children_migration);
+/// Run up or down of operation. If operation or its migration has timeout,
+/// timeout settings of connection are changed while operation runs and
+/// operation is cancelled once timeout is elapsed. Cancelled operation can
+/// still be running on database so settings are not restored and connection
+/// is discarded instead. If recorder is provided, statements executed by
+/// operation are recorded
+async fn run_operation_with_timeout<DB, M>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    migration: &BoxMigration<DB>,
+    operation: &dyn Operation<DB>,
+    direction: Direction,
+    recorder: Option<&StatementRecorder>,
+) -> Result<(), Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+{
+    let settings = match operation.timeout().or_else(|| migration.timeout()) {
+        Some(timeout) => {
+            Some((
+                timeout,
+                migrator.set_operation_timeout(connection, timeout).await?,
+            ))
         }
-        // in second loop add recursive children of parent due to replace
-        for (child, &parent) in &replaces_child_parent_hash_map {
-            let children_migration = if child.is_virtual() {
-                self.migrations()
-                    .iter()
-                    .find(|&search_migration| search_migration == child)
-                    .ok_or(Error::PlanError {
-                        message: "Failed finding non virtual migration for virtual migration"
-                            .to_string(),
-                    })?
-            } else {
-                child
-            };
-            populate_replace_recursive(&mut replace_children, parent, children_migration)?;
+        None => None,
+    };
+    let future = match direction {
+        Direction::Apply => operation.up(connection),
+        Direction::Revert => operation.down(connection),
+    };
+    let future = async {
+        match recorder {
+            Some(recorder) => recorder.record(future).await,
+            None => future.await,
         }
-        // Hashmap which contains key as migration and value is vector of migration
-        // which should run before this migration. One migration can have
-        // multiple run before migration
-        let mut run_before_child_parent_hash_map = HashMap::<_, Vec<_>>::new();
-
-        for parent_migration in self.migrations() {
-            for run_before_migration in parent_migration.run_before() {
-                run_before_child_parent_hash_map
-                    .entry(run_before_migration)
-                    .or_default()
-                    .push(parent_migration);
-            }
+    };
+    let Some((timeout, settings)) = settings else {
+        return future.await;
+    };
+    let result = crate::timer::timeout(timeout, future).await;
+    let result = match result {
+        Ok(Some(Err(error))) if migrator.is_timeout_error(&error) => {
+            Err(Error::OperationTimeout { timeout })
         }
+        Ok(Some(result)) => result,
+        Ok(None) => return Err(Error::OperationCancelled { timeout }),
+        // operation is not run when timer is unavailable
+        Err(error) => Err(error),
+    };
+    // settings are restored even if operation failed but error of operation
+    // is returned instead of error of restoring settings. Settings changed
+    // inside failed transaction are also reverted by its rollback
+    let restore_result = migrator
+        .restore_operation_timeout(connection, settings)
+        .await;
+    result.and(restore_result)
+}
 
-        let mut migration_list = Vec::new();
-
-        // keep looping until all migration are added to migration list. In each loop
-        // check if any migration can be added to migration list or not. A migration
-        // can be added if all its parents are already added to migration list
-        let original_migration_length = self.migrations().len();
-        while migration_list.len() != original_migration_length {
-            let loop_initial_migration_list_length = migration_list.len();
-            for migration in self.migrations() {
-                // check if all parents and run before migration are already added to
-                // migration list and if it replaces any migration than that migration
-                // should be added to migration list as well before adding this migration
-                // to migration list. Also if this migration have children due to replace
-                // than their parents and run before should be added to migration list
-                // before adding this migration to migration list
-                let all_required_added = !migration_list.contains(&migration)
-                    && migration
-                        .parents()
-                        .iter()
-                        .all(|parent_migration| migration_list.contains(&parent_migration))
-                    && run_before_child_parent_hash_map
-                        .get(migration)
-                        .unwrap_or(&vec![])
-                        .iter()
-                        .all(|run_before_migration| migration_list.contains(run_before_migration))
-                    && replaces_child_parent_hash_map
-                        .get(migration)
-                        .is_none_or(|replace_migration| migration_list.contains(replace_migration))
-                    && replace_children.get(migration).is_none_or(|children| {
-                        // if children are present than their parents and run before should be
-                        // added to migration list before adding replace migration
-                        children.iter().all(|&child| {
-                            child
-                                .parents()
-                                .iter()
-                                .all(|child_parent| migration_list.contains(&child_parent))
-                                && run_before_child_parent_hash_map
-                                    .get(child)
-                                    .unwrap_or(&vec![])
-                                    .iter()
-                                    .all(|run_before_migration| {
-                                        migration_list.contains(run_before_migration)
-                                            || children.contains(run_before_migration)
-                                    })
-                        })
-                    });
-                if all_required_added {
-                    migration_list.push(migration);
-                }
-            }
-
-            // if no migration is added in this loop than it means there is a deadlock
-            // and we cannot proceed further
-            if loop_initial_migration_list_length == migration_list.len() {
-                return Err(Error::PlanError {
-                    message: "reached deadlock stage during plan generation".to_string(),
-                });
-            }
-        }
+/// Validate plan which is run inside single transaction. Such plan cannot
+/// continue on error, requires transactional DDL and cannot contain non atomic
+/// migration
+async fn validate_single_transaction<DB, M>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    migrations: &MigrationVec<'_, DB>,
+    plan: &Plan,
+) -> Result<(), Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+{
+    if plan.continue_on_error {
+        return Err(Error::PlanError {
+            message: "single transaction plan cannot continue on error".to_string(),
+        });
+    }
+    if !migrator.is_transactional_ddl_supported(connection).await? {
+        return Err(Error::PlanError {
+            message: "database does not support transactional DDL required for single transaction \
+                      plan"
+                .to_string(),
+        });
+    }
+    if let Some(migration) = migrations.iter().find(|migration| !migration.is_atomic()) {
+        return Err(Error::PlanError {
+            message: format!(
+                "non atomic migration {}:{} cannot be run in single transaction",
+                migration.app(),
+                migration.name()
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Run migrations of plan on connection and return report of migrations
+/// which succeeded, failed or are skipped
+#[expect(clippy::too_many_lines)]
+async fn run_plan_migrations<DB, M>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    migrations: MigrationVec<'_, DB>,
+    applied_migration_sql_rows: &[AppliedMigrationSqlRow],
+    plan: &Plan,
+) -> Result<RunReport, Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+{
+    let progress_supported = migrator.is_operation_progress_supported();
+    let mut report = RunReport::default();
+    // app and name of migrations which failed or are skipped so migrations
+    // depending upon them are skipped
+    let mut blocked = Vec::new();
+    let direction = plan.direction();
+    for migration in migrations {
+        let app_name = (migration.app().to_string(), migration.name().to_string());
+        let span = tracing::info_span!(
+            "migration",
+            app = migration.app(),
+            name = migration.name(),
+            direction = direction.as_str(),
+            atomic = migration.is_atomic(),
+            fake = plan.fake,
+            attempts = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
+        if plan.continue_on_error
+            && depends_on_blocked(migration, &blocked, migrator.migrations(), &plan.plan_type)
+        {
+            tracing::debug!("skipping {} : {}", migration.app(), migration.name());
+            span.record("outcome", "skipped");
+            blocked.push(app_name.clone());
+            report.skipped.push(app_name);
+            continue;
+        }
+        migrator.refresh_lock(connection).await?;
+        // number of operations which are applied if migration is partially applied
+        let applied_operations = applied_migration_sql_rows
+            .iter()
+            .find(|applied_migration| *applied_migration == migration)
+            .and_then(AppliedMigrationSqlRow::applied_operations);
+        // progress of operations is only stored for non atomic migration since
+        // atomic migration is applied or reverted as whole
+        let track_progress = progress_supported && !plan.fake && !migration.is_atomic();
+        let mut run_migration = async || -> Result<(), Error> {
+            for listener in migrator.listeners() {
+                listener
+                    .before_migration(connection, migration.as_ref(), direction)
+                    .await?;
+            }
+            match plan.plan_type {
+                PlanType::Apply => {
+                    tracing::debug!("applying {} : {}", migration.app(), migration.name());
+                    let event = if plan.fake {
+                        HistoryEvent::FakeApply
+                    } else {
+                        HistoryEvent::Apply
+                    };
+                    // skip operations which are already applied by partially applied
+                    // migration
+                    let operations = migration
+                        .operations()
+                        .into_iter()
+                        .enumerate()
+                        .skip(applied_operations.unwrap_or_default());
+                    let start_time = Instant::now();
+                    if migration.is_atomic() {
+                        let mut transaction = connection.begin().await?;
+                        if !plan.fake {
+                            for (operation_index, operation) in operations {
+                                run_operation(
+                                    migrator,
+                                    &mut transaction,
+                                    migration,
+                                    operation.as_ref(),
+                                    operation_index,
+                                    Direction::Apply,
+                                    None,
+                                )
+                                .await
+                                .map_err(operation_failed(
+                                    migration,
+                                    operation_index,
+                                    Direction::Apply,
+                                    true,
+                                ))?;
+                                for listener in migrator.listeners() {
+                                    listener
+                                        .after_operation(
+                                            &mut transaction,
+                                            migration.as_ref(),
+                                            Direction::Apply,
+                                            operation_index,
+                                        )
+                                        .await?;
+                                }
+                            }
+                        }
+                        if applied_operations.is_some() {
+                            migrator
+                                .update_applied_operations(&mut transaction, migration, None)
+                                .await?;
+                            if !plan.fake {
+                                migrator
+                                    .update_migration_duration(
+                                        &mut transaction,
+                                        migration,
+                                        start_time.elapsed(),
+                                    )
+                                    .await?;
+                            }
+                        } else if plan.fake {
+                            migrator
+                                .add_migration_to_db_table(&mut transaction, migration)
+                                .await?;
+                        } else {
+                            migrator
+                                .add_migration_to_db_table_with_duration(
+                                    &mut transaction,
+                                    migration,
+                                    start_time.elapsed(),
+                                )
+                                .await?;
+                        }
+                        migrator
+                            .add_history_to_db_table(&mut transaction, migration, event)
+                            .await?;
+                        transaction.commit().await?;
+                    } else {
+                        // add migration before running operations so migration is left
+                        // partially applied if any operation fails
+                        if track_progress && applied_operations.is_none() {
+                            migrator
+                                .add_migration_to_db_table(connection, migration)
+                                .await?;
+                            migrator
+                                .update_applied_operations(connection, migration, Some(0))
+                                .await?;
+                        }
+                        if !plan.fake {
+                            for (operation_index, operation) in operations {
+                                run_operation(
+                                    migrator,
+                                    connection,
+                                    migration,
+                                    operation.as_ref(),
+                                    operation_index,
+                                    Direction::Apply,
+                                    None,
+                                )
+                                .await
+                                .map_err(operation_failed(
+                                    migration,
+                                    operation_index,
+                                    Direction::Apply,
+                                    false,
+                                ))?;
+                                if track_progress {
+                                    migrator
+                                        .update_applied_operations(
+                                            connection,
+                                            migration,
+                                            Some(operation_index + 1),
+                                        )
+                                        .await?;
+                                }
+                                for listener in migrator.listeners() {
+                                    listener
+                                        .after_operation(
+                                            connection,
+                                            migration.as_ref(),
+                                            Direction::Apply,
+                                            operation_index,
+                                        )
+                                        .await?;
+                                }
+                            }
+                        }
+                        if track_progress || applied_operations.is_some() {
+                            migrator
+                                .update_applied_operations(connection, migration, None)
+                                .await?;
+                            if !plan.fake {
+                                migrator
+                                    .update_migration_duration(
+                                        connection,
+                                        migration,
+                                        start_time.elapsed(),
+                                    )
+                                    .await?;
+                            }
+                        } else if plan.fake {
+                            migrator
+                                .add_migration_to_db_table(connection, migration)
+                                .await?;
+                        } else {
+                            migrator
+                                .add_migration_to_db_table_with_duration(
+                                    connection,
+                                    migration,
+                                    start_time.elapsed(),
+                                )
+                                .await?;
+                        }
+                        migrator
+                            .add_history_to_db_table(connection, migration, event)
+                            .await?;
+                    }
+                }
+                PlanType::Revert => {
+                    tracing::debug!("reverting {} : {}", migration.app(), migration.name());
+                    let event = if plan.fake {
+                        HistoryEvent::FakeRevert
+                    } else {
+                        HistoryEvent::Revert
+                    };
+
+                    let mut operations = migration.operations();
+                    // only applied operations of partially applied migration are reverted
+                    if let Some(applied_operations) = applied_operations {
+                        operations.truncate(applied_operations);
+                    }
+                    // Reverse operation since last applied operation need to be reverted
+                    // first
+                    let operations = operations.into_iter().enumerate().rev();
+
+                    if migration.is_atomic() {
+                        let mut transaction = connection.begin().await?;
+                        if !plan.fake {
+                            for (operation_index, operation) in operations {
+                                run_operation(
+                                    migrator,
+                                    &mut transaction,
+                                    migration,
+                                    operation.as_ref(),
+                                    operation_index,
+                                    Direction::Revert,
+                                    None,
+                                )
+                                .await
+                                .map_err(operation_failed(
+                                    migration,
+                                    operation_index,
+                                    Direction::Revert,
+                                    true,
+                                ))?;
+                                for listener in migrator.listeners() {
+                                    listener
+                                        .after_operation(
+                                            &mut transaction,
+                                            migration.as_ref(),
+                                            Direction::Revert,
+                                            operation_index,
+                                        )
+                                        .await?;
+                                }
+                            }
+                        }
+                        migrator
+                            .delete_migration_from_db_table(&mut transaction, migration)
+                            .await?;
+                        migrator
+                            .add_history_to_db_table(&mut transaction, migration, event)
+                            .await?;
+                        transaction.commit().await?;
+                    } else {
+                        if !plan.fake {
+                            for (operation_index, operation) in operations {
+                                run_operation(
+                                    migrator,
+                                    connection,
+                                    migration,
+                                    operation.as_ref(),
+                                    operation_index,
+                                    Direction::Revert,
+                                    None,
+                                )
+                                .await
+                                .map_err(operation_failed(
+                                    migration,
+                                    operation_index,
+                                    Direction::Revert,
+                                    false,
+                                ))?;
+                                // operations before reverted operation remain applied
+                                if track_progress {
+                                    migrator
+                                        .update_applied_operations(
+                                            connection,
+                                            migration,
+                                            Some(operation_index),
+                                        )
+                                        .await?;
+                                }
+                                for listener in migrator.listeners() {
+                                    listener
+                                        .after_operation(
+                                            connection,
+                                            migration.as_ref(),
+                                            Direction::Revert,
+                                            operation_index,
+                                        )
+                                        .await?;
+                                }
+                            }
+                        }
+                        migrator
+                            .delete_migration_from_db_table(connection, migration)
+                            .await?;
+                        migrator
+                            .add_history_to_db_table(connection, migration, event)
+                            .await?;
+                    }
+                }
+            }
+            Ok(())
+        };
+        // only whole transaction of atomic migration can be retried. Migration run
+        // inside single transaction is not retried since transaction of plan is
+        // also aborted by transient error. Transaction is rolled back by transient
+        // error even when database do not support transactional DDL so such
+        // migration is also retried
+        let retry_policy = (migration.is_atomic() && !plan.fake && !plan.single_transaction)
+            .then(|| migrator.retry_policy());
+        let start_time = Instant::now();
+        let result = async {
+            let mut attempt = 1;
+            loop {
+                let result = run_migration().await;
+                if let Err(error) = &result
+                    && let Some(delay) = retry_policy
+                        .as_ref()
+                        .and_then(|policy| policy.retry_delay(migrator, attempt, error))
+                {
+                    tracing::debug!(
+                        "retrying {} : {} after {delay:?} due to error {error}",
+                        migration.app(),
+                        migration.name()
+                    );
+                    match crate::timer::sleep(delay) {
+                        Ok(sleep) => {
+                            sleep.await;
+                            attempt += 1;
+                            continue;
+                        }
+                        // error of migration is returned when retry cannot be
+                        // delayed
+                        Err(timer_error) => {
+                            tracing::error!(
+                                "cannot retry {} : {} due to error {timer_error}",
+                                migration.app(),
+                                migration.name()
+                            );
+                        }
+                    }
+                }
+                span.record("attempts", attempt);
+                break result;
+            }
+        }
+        .instrument(span.clone())
+        .await;
+        record_span_outcome(&span, start_time, result_outcome(&result));
+        // connection of cancelled operation cannot be used anymore
+        let result = match result {
+            Err(error) if error.is_connection_discarded() => return Err(error),
+            result => result,
+        };
+        // migration is already committed so error of listener is only logged
+        // instead of changing outcome of migration
+        if result.is_ok() {
+            for listener in migrator.listeners() {
+                if let Err(error) = listener
+                    .after_migration(connection, migration.as_ref(), direction)
+                    .await
+                {
+                    tracing::error!(
+                        "after migration listener failed for {} : {} with error {error}",
+                        migration.app(),
+                        migration.name()
+                    );
+                }
+            }
+        }
+        if let Err(error) = &result {
+            for listener in migrator.listeners() {
+                listener
+                    .on_error(connection, migration.as_ref(), direction, error)
+                    .await;
+            }
+        }
+        match result {
+            Ok(()) => report.succeeded.push(app_name),
+            Err(error) if plan.continue_on_error => {
+                tracing::debug!(
+                    "failed {} : {} with error {error}",
+                    migration.app(),
+                    migration.name()
+                );
+                // migrations replaced by failed migration are also considered as
+                // failed since they are not applied in its place
+                for replaced in migration.replaces() {
+                    blocked.push((replaced.app().to_string(), replaced.name().to_string()));
+                }
+                blocked.push(app_name.clone());
+                report.failed.push((app_name.0, app_name.1, error));
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(report)
+}
 
-        // if plan is provided than modify migration list according to plan else
-        // return all migration in order of apply
-        if let Some(some_plan) = plan {
-            self.ensure_migration_table_exists(connection).await?;
-
-            // fetch applied migration from database
-            let applied_migration_sql_rows =
-                self.fetch_applied_migration_from_db(connection).await?;
-            let mut applied_migrations = Vec::new();
-            for migration in self.migrations() {
-                if applied_migration_sql_rows
+/// Acquire migration lock on connection and run migrations of plan while
+/// holding lock. Lock is released before returning result and connection is
+/// discarded if lock cannot be released
+pub(crate) async fn run_plan_with_lock<DB, M, C>(
+    migrator: &M,
+    connection: C,
+    plan: &Plan,
+) -> Result<RunReport, Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+    C: LockConnection<DB>,
+{
+    let span = tracing::info_span!(
+        "plan",
+        direction = plan.direction().as_str(),
+        fake = plan.fake,
+        single_transaction = plan.single_transaction,
+        continue_on_error = plan.continue_on_error,
+        migrations = tracing::field::Empty,
+        duration_ms = tracing::field::Empty,
+        outcome = tracing::field::Empty,
+    );
+    let start_time = Instant::now();
+    let result = async {
+        tracing::debug!("running plan {:?}", plan);
+        let mut guard = LockGuard::acquire(
+            migrator,
+            connection,
+            plan.lock_timeout.or(migrator.lock_timeout()),
+        )
+        .await?;
+        let connection = &mut *guard;
+        // store result of applying migration so that we can unlock lock before
+        // returning result or resuming panic
+        let result = catch_unwind(async {
+            let (migrations, applied_migration_sql_rows) =
+                generate_plan_with_applied_rows(migrator, connection, Some(plan)).await?;
+            span.record("migrations", migrations.len());
+            if plan.single_transaction {
+                validate_single_transaction(migrator, connection, &migrations, plan).await?;
+            }
+            if plan.accept_checksum {
+                update_changed_checksums(migrator, connection, &applied_migration_sql_rows).await?;
+            }
+            let plan_migrations = migrations
+                .iter()
+                .map(|&migration| migration.as_ref())
+                .collect::<Vec<_>>();
+            for listener in migrator.listeners() {
+                listener
+                    .before_plan(connection, plan.direction(), &plan_migrations)
+                    .await?;
+            }
+            let report = if plan.single_transaction {
+                // each atomic migration uses savepoint inside transaction of plan
+                let mut transaction = connection.begin().await?;
+                let report = run_plan_migrations(
+                    migrator,
+                    &mut transaction,
+                    migrations,
+                    &applied_migration_sql_rows,
+                    plan,
+                )
+                .await?;
+                transaction.commit().await?;
+                report
+            } else {
+                run_plan_migrations(
+                    migrator,
+                    connection,
+                    migrations,
+                    &applied_migration_sql_rows,
+                    plan,
+                )
+                .await?
+            };
+            for listener in migrator.listeners() {
+                listener
+                    .after_plan(connection, plan.direction(), &report)
+                    .await?;
+            }
+            Ok(report)
+        })
+        .await;
+        // unlock lock before returning result of applying migration
+        guard.release_unwind(result).await
+    }
+    .instrument(span.clone())
+    .await;
+    let outcome = match &result {
+        Ok(report) if report.is_success() => "success",
+        Ok(_) | Err(_) => "failure",
+    };
+    record_span_outcome(&span, start_time, outcome);
+    result
+}
+
+/// Update stored checksum of applied migrations which are modified after they
+/// were applied to their current checksum
+async fn update_changed_checksums<DB, M>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    applied_migration_sql_rows: &[AppliedMigrationSqlRow],
+) -> Result<(), Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+{
+    for migration in migrator.migrations() {
+        if let Some(applied_migration_sql_row) = applied_migration_sql_rows
+            .iter()
+            .find(|applied_migration| *applied_migration == migration)
+            && let (Some(stored_checksum), Some(current_checksum)) =
+                (applied_migration_sql_row.checksum(), migration.checksum())
+            && stored_checksum != current_checksum
+        {
+            tracing::warn!(
+                "accepting changed checksum of migration {} : {}",
+                migration.app(),
+                migration.name()
+            );
+            migrator
+                .update_migration_checksum(connection, migration)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Generate migration plan according to plan along with applied migration
+/// rows which are fetched for generating plan. Applied migration rows are
+/// only fetched when plan is provided
+#[expect(clippy::too_many_lines)]
+async fn generate_plan_with_applied_rows<'migrator, DB, M>(
+    migrator: &'migrator M,
+    connection: &mut <DB as Database>::Connection,
+    plan: Option<&Plan>,
+) -> Result<(MigrationVec<'migrator, DB>, Vec<AppliedMigrationSqlRow>), Error>
+where
+    DB: Database,
+    M: Migrate<DB> + ?Sized,
+{
+    if migrator.migrations().is_empty() {
+        return Err(Error::PlanError {
+            message: "no migration are added to migration list".to_string(),
+        });
+    }
+    // if there is any virtual migration which is not replaced than return
+    // error since virtual migration should only be used for replacing
+    // another migration
+    if migrator
+        .migrations()
+        .iter()
+        .any(|migration| migration.is_virtual())
+    {
+        return Err(Error::PlanError {
+            message: "virtual migrations which is not replaced is present".to_string(),
+        });
+    }
+
+    tracing::debug!("generating {:?} migration plan", plan);
+
+    // hashmap which contains key as child migration and value is parent
+    // migration which replaces this child migration. One migration can
+    // only have one parent
+    let mut replaces_child_parent_hash_map = HashMap::new();
+
+    for parent_migration in migrator.migrations() {
+        for child_migration in parent_migration.replaces() {
+            let child_name = format!("{}:{}", child_migration.app(), child_migration.name());
+            if replaces_child_parent_hash_map
+                .insert(child_migration, parent_migration)
+                .is_some()
+            {
+                return Err(Error::PlanError {
+                    message: format!("migration {child_name} replaced multiple times"),
+                });
+            }
+        }
+    }
+
+    // Hashmap which contains key as migration and value is vector of migration
+    // which are children of this migration due to replace. One migration can
+    // have multiple children
+    let mut replace_children = HashMap::<_, Vec<_>>::new();
+    // in first loop add direct children of parent due to replace
+    for (child, &parent) in &replaces_child_parent_hash_map {
+        // if child is virtual than we need to find non virtual migration
+        let children_migration = if child.is_virtual() {
+            migrator
+                .migrations()
+                .iter()
+                .find(|&search_migration| search_migration == child)
+                .ok_or(Error::PlanError {
+                    message: "Failed finding non virtual migration for virtual migration"
+                        .to_string(),
+                })?
+        } else {
+            child
+        };
+        replace_children
+            .entry(parent)
+            .or_default()
+            .push(children_migration);
+    }
+    // in second loop add recursive children of parent due to replace
+    for (child, &parent) in &replaces_child_parent_hash_map {
+        let children_migration = if child.is_virtual() {
+            migrator
+                .migrations()
+                .iter()
+                .find(|&search_migration| search_migration == child)
+                .ok_or(Error::PlanError {
+                    message: "Failed finding non virtual migration for virtual migration"
+                        .to_string(),
+                })?
+        } else {
+            child
+        };
+        populate_replace_recursive(&mut replace_children, parent, children_migration)?;
+    }
+    // Hashmap which contains key as migration and value is vector of migration
+    // which should run before this migration. One migration can have
+    // multiple run before migration
+    let mut run_before_child_parent_hash_map = HashMap::<_, Vec<_>>::new();
+
+    for parent_migration in migrator.migrations() {
+        for run_before_migration in parent_migration.run_before() {
+            run_before_child_parent_hash_map
+                .entry(run_before_migration)
+                .or_default()
+                .push(parent_migration);
+        }
+    }
+
+    let mut migration_list = Vec::new();
+
+    // keep looping until all migration are added to migration list. In each loop
+    // check if any migration can be added to migration list or not. A migration
+    // can be added if all its parents are already added to migration list
+    let original_migration_length = migrator.migrations().len();
+    while migration_list.len() != original_migration_length {
+        let loop_initial_migration_list_length = migration_list.len();
+        for migration in migrator.migrations() {
+            // check if all parents and run before migration are already added to
+            // migration list and if it replaces any migration than that migration
+            // should be added to migration list as well before adding this migration
+            // to migration list. Also if this migration have children due to replace
+            // than their parents and run before should be added to migration list
+            // before adding this migration to migration list
+            let all_required_added = !migration_list.contains(&migration)
+                && migration
+                    .parents()
+                    .iter()
+                    .all(|parent_migration| migration_list.contains(&parent_migration))
+                && run_before_child_parent_hash_map
+                    .get(migration)
+                    .unwrap_or(&vec![])
                     .iter()
-                    .any(|sqlx_migration| sqlx_migration == migration)
+                    .all(|run_before_migration| migration_list.contains(run_before_migration))
+                && replaces_child_parent_hash_map
+                    .get(migration)
+                    .is_none_or(|replace_migration| migration_list.contains(replace_migration))
+                && replace_children.get(migration).is_none_or(|children| {
+                    // if children are present than their parents and run before should be
+                    // added to migration list before adding replace migration
+                    children.iter().all(|&child| {
+                        child
+                            .parents()
+                            .iter()
+                            .all(|child_parent| migration_list.contains(&child_parent))
+                            && run_before_child_parent_hash_map
+                                .get(child)
+                                .unwrap_or(&vec![])
+                                .iter()
+                                .all(|run_before_migration| {
+                                    migration_list.contains(run_before_migration)
+                                        || children.contains(run_before_migration)
+                                })
+                    })
+                });
+            if all_required_added {
+                migration_list.push(migration);
+            }
+        }
+
+        // if no migration is added in this loop than it means there is a deadlock
+        // and we cannot proceed further
+        if loop_initial_migration_list_length == migration_list.len() {
+            return Err(Error::PlanError {
+                message: "reached deadlock stage during plan generation".to_string(),
+            });
+        }
+    }
+
+    // if plan is provided than modify migration list according to plan else
+    // return all migration in order of apply
+    let mut applied_migration_sql_rows = Vec::new();
+    if let Some(some_plan) = plan {
+        migrator.ensure_migration_table_exists(connection).await?;
+
+        // fetch applied migration from database
+        applied_migration_sql_rows = migrator.fetch_applied_migration_from_db(connection).await?;
+        let mut applied_migrations = Vec::new();
+        let mut partially_applied_migrations = Vec::new();
+        for migration in migrator.migrations() {
+            if let Some(applied_migration_sql_row) = applied_migration_sql_rows
+                .iter()
+                .find(|sqlx_migration| sqlx_migration == &migration)
+            {
+                // Check if applied migration is modified after it was applied by
+                // comparing stored checksum with current checksum. Checksum are only
+                // compared when both of them are present so migrations applied before
+                // checksum was stored are not checked. Modified migration only
+                // matters when migrations are applied so revert and fake plan and
+                // plan which accepts changed checksum are not checked
+                if let PlanType::Apply = some_plan.plan_type
+                    && !some_plan.fake
+                    && !some_plan.accept_checksum
+                    && let (Some(stored_checksum), Some(current_checksum)) =
+                        (applied_migration_sql_row.checksum(), migration.checksum())
+                    && stored_checksum != current_checksum
                 {
-                    applied_migrations.push(migration);
+                    return Err(Error::ChecksumMismatch {
+                        app: migration.app().to_string(),
+                        name: migration.name().to_string(),
+                        stored_checksum: stored_checksum.to_string(),
+                        current_checksum,
+                    });
+                }
+                if applied_migration_sql_row.is_partially_applied() {
+                    partially_applied_migrations.push(migration);
                 }
+                applied_migrations.push(migration);
             }
+        }
 
-            // Check if any child migration is applied before its parent migration
-            // according to parents and run before field. If yes than return error
-            for &migration in &applied_migrations {
-                let mut parents = vec![];
-                if let Some(run_before_list) = run_before_child_parent_hash_map.get(migration) {
-                    for &run_before in run_before_list {
-                        parents.push(run_before);
-                    }
+        // Check if any child migration is applied before its parent migration
+        // according to parents and run before field. If yes than return error
+        for &migration in &applied_migrations {
+            let mut parents = vec![];
+            if let Some(run_before_list) = run_before_child_parent_hash_map.get(migration) {
+                for &run_before in run_before_list {
+                    parents.push(run_before);
                 }
-                let main_parents = migration.parents();
-                for parent in &main_parents {
-                    parents.push(parent);
+            }
+            let main_parents = migration.parents();
+            for parent in &main_parents {
+                parents.push(parent);
+            }
+            for parent in parents {
+                let recursive_vec = get_recursive_replaces(&replaces_child_parent_hash_map, parent);
+                if !applied_migrations
+                    .iter()
+                    .any(|applied| recursive_vec.contains(applied))
+                {
+                    return Err(Error::PlanError {
+                        message: format!(
+                            "children migration {}:{} applied before its parent migration {}:{}",
+                            migration.app(),
+                            migration.name(),
+                            parent.app(),
+                            parent.name()
+                        ),
+                    });
                 }
-                for parent in parents {
-                    let recursive_vec =
-                        get_recursive_replaces(&replaces_child_parent_hash_map, parent);
-                    if !applied_migrations
-                        .iter()
-                        .any(|applied| recursive_vec.contains(applied))
-                    {
+            }
+        }
+
+        // Check if any migration and its replaces are applied together or not
+        // If yes than return error
+        for migration in migration_list.clone() {
+            // Check if this migration have any children due to replace
+            if let Some(children) = replace_children.get(&migration) {
+                // Check if any one of replaced migration is applied or not
+                let replaces_applied = children
+                    .iter()
+                    .any(|&replace_migration| applied_migrations.contains(&replace_migration));
+
+                // If replaces migration is applied than we cannot apply this migration
+                // If replaces migration is not applied than we can remove all replaced
+                // migration from migration list since this migration will apply in
+                // place of them
+                if replaces_applied {
+                    // Errors out if this migration is also applied since both
+                    // migration and its replaces cannot be applied together
+                    if applied_migrations.contains(&migration) {
                         return Err(Error::PlanError {
                             message: format!(
-                                "children migration {}:{} applied before its parent migration \
-                                 {}:{}",
+                                "migration {}:{} and its replaces are applied together",
                                 migration.app(),
                                 migration.name(),
-                                parent.app(),
-                                parent.name()
                             ),
                         });
                     }
-                }
-            }
-
-            // Check if any migration and its replaces are applied together or not
-            // If yes than return error
-            for migration in migration_list.clone() {
-                // Check if this migration have any children due to replace
-                if let Some(children) = replace_children.get(&migration) {
-                    // Check if any one of replaced migration is applied or not
-                    let replaces_applied = children
-                        .iter()
-                        .any(|&replace_migration| applied_migrations.contains(&replace_migration));
-
-                    // If replaces migration is applied than we cannot apply this migration
-                    // If replaces migration is not applied than we can remove all replaced
-                    // migration from migration list since this migration will apply in
-                    // place of them
-                    if replaces_applied {
-                        // Errors out if this migration is also applied since both
-                        // migration and its replaces cannot be applied together
-                        if applied_migrations.contains(&migration) {
-                            return Err(Error::PlanError {
-                                message: format!(
-                                    "migration {}:{} and its replaces are applied together",
-                                    migration.app(),
-                                    migration.name(),
-                                ),
-                            });
-                        }
-                        migration_list.retain(|&plan_migration| migration != plan_migration);
-                    } else {
-                        // remove all replaced migration from migration list since this
-                        // migration will apply in place of them
-                        for replaced_migration in children {
-                            migration_list
-                                .retain(|plan_migration| replaced_migration != plan_migration);
-                        }
+                    migration_list.retain(|&plan_migration| migration != plan_migration);
+                } else {
+                    // remove all replaced migration from migration list since this
+                    // migration will apply in place of them
+                    for replaced_migration in children {
+                        migration_list
+                            .retain(|plan_migration| replaced_migration != plan_migration);
                     }
                 }
             }
+        }
 
-            process_plan(
-                &mut migration_list,
-                &applied_migrations,
-                some_plan,
-                self.migrations(),
-            )?;
+        // partially applied migrations are applied again so their remaining
+        // operations are run while they are reverted like applied migrations
+        if let PlanType::Apply = some_plan.plan_type {
+            applied_migrations
+                .retain(|migration| !partially_applied_migrations.contains(migration));
         }
 
-        Ok(migration_list)
+        process_plan(
+            &mut migration_list,
+            &applied_migrations,
+            some_plan,
+            migrator.migrations(),
+        )?;
+    }
+
+    Ok((migration_list, applied_migration_sql_rows))
+}
+
+/// The [`Migrate`] trait defines methods to manage and apply database
+/// migrations according to a given plan.
+///
+/// This trait combines the functionalities of the [`Info`] and
+/// [`DatabaseOperation`] traits, providing a full set of migration
+/// capabilities. All methods have default implementations, meaning no explicit
+/// implementation is required. Additionally, all methods are database-agnostic.
+#[async_trait::async_trait]
+pub trait Migrate<DB>: Info<DB> + DatabaseOperation<DB> + Send + Sync
+where
+    DB: Database,
+{
+    /// Returns policy used for retrying atomic migration which failed with
+    /// transient error while running plan
+    ///
+    /// By default, migration is not retried
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// Returns timeout used for acquiring migration lock while running plan
+    /// which do not set its own lock timeout using [`Plan::lock_timeout`]
+    ///
+    /// By default, lock is waited forever
+    fn lock_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns listeners which are notified while running plan
+    ///
+    /// By default, no listener is notified
+    fn listeners(&self) -> &[Box<dyn MigrationListener<DB>>] {
+        &[]
+    }
+
+    /// Generate migration plan according to plan.
+    ///
+    /// Returns a vector of migration. If plan is none than it will generate
+    /// plan with all migrations in order of apply
+    async fn generate_migration_plan(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        plan: Option<&Plan>,
+    ) -> MigrationVecResult<'_, DB> {
+        let (migrations, _) = generate_plan_with_applied_rows(self, connection, plan).await?;
+        Ok(migrations)
+    }
+
+    /// Generate migration plan according to plan using connection acquired
+    /// from pool
+    ///
+    /// # Errors
+    /// If failed to acquire connection or failed to generate plan
+    async fn generate_migration_plan_with_pool(
+        &self,
+        pool: &Pool<DB>,
+        plan: Option<&Plan>,
+    ) -> MigrationVecResult<'_, DB> {
+        let mut connection = pool.acquire().await?;
+        self.generate_migration_plan(&mut connection, plan).await
     }
 
     /// Run provided plan migrations
     ///
+    /// Migration lock is released even if migration fails or panics. Borrowed
+    /// connection cannot be closed by migrator so if returned future is
+    /// cancelled, lock remains held by session of connection till caller
+    /// closes it. Similarly if returned error is
+    /// [`Error::is_connection_discarded`], statement of cancelled operation
+    /// can still be running and lock is not released so caller must close
+    /// connection instead of reusing it. Use [`Migrate::run_with_pool`] which
+    /// closes its connection in such case.
+    ///
     /// # Errors
-    /// If failed to run provided plan migrations
+    /// If failed to run provided plan migrations. If plan continues on error
+    /// and any migration failed [`Error::MigrationsFailed`] is returned
     async fn run(
         &self,
         connection: &mut <DB as Database>::Connection,
         plan: &Plan,
     ) -> Result<(), Error> {
-        tracing::debug!("running plan {:?}", plan);
-        self.lock(connection).await?;
-        // store result of applying migration so that we can unlock lock before
-        // returning result
-        let result = async {
-            for migration in self.generate_migration_plan(connection, Some(plan)).await? {
-                match plan.plan_type {
-                    PlanType::Apply => {
-                        tracing::debug!("applying {} : {}", migration.app(), migration.name());
-                        let operations = migration.operations();
-                        if migration.is_atomic() {
-                            let mut transaction = connection.begin().await?;
-                            if !plan.fake {
-                                for operation in operations {
-                                    operation.up(&mut transaction).await?;
-                                }
-                            }
-                            self.add_migration_to_db_table(&mut transaction, migration)
-                                .await?;
-                            transaction.commit().await?;
-                        } else {
-                            if !plan.fake {
-                                for operation in operations {
-                                    operation.up(connection).await?;
-                                }
+        let report = self.run_with_report(connection, plan).await?;
+        if !report.is_success() {
+            return Err(Error::MigrationsFailed {
+                report: Box::new(report),
+            });
+        }
+        Ok(())
+    }
+
+    /// Run provided plan migrations on connection acquired from pool
+    ///
+    /// Connection is used for holding migration lock while running plan and
+    /// is returned to pool once lock is released. If lock cannot be released
+    /// connection is closed instead so lock held by its session is released
+    ///
+    /// # Errors
+    /// If failed to acquire connection or failed to run provided plan
+    /// migrations
+    async fn run_with_pool(&self, pool: &Pool<DB>, plan: &Plan) -> Result<(), Error> {
+        let report = self.run_with_report_with_pool(pool, plan).await?;
+        if !report.is_success() {
+            return Err(Error::MigrationsFailed {
+                report: Box::new(report),
+            });
+        }
+        Ok(())
+    }
+
+    /// Run provided plan migrations and return report of migrations which
+    /// succeeded, failed or are skipped
+    ///
+    /// Similar to [`Migrate::run`], if returned future is cancelled lock
+    /// remains held by session of borrowed connection till caller closes it
+    ///
+    /// # Errors
+    /// If failed to run provided plan migrations. If plan continues on error
+    /// failure of migration is stored in report instead of returning error
+    async fn run_with_report(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        plan: &Plan,
+    ) -> Result<RunReport, Error> {
+        run_plan_with_lock(self, BorrowedConnection(connection), plan).await
+    }
+
+    /// Run provided plan migrations on connection acquired from pool and
+    /// return report of migrations which succeeded, failed or are skipped
+    ///
+    /// # Errors
+    /// If failed to acquire connection or failed to run provided plan
+    /// migrations
+    async fn run_with_report_with_pool(
+        &self,
+        pool: &Pool<DB>,
+        plan: &Plan,
+    ) -> Result<RunReport, Error> {
+        let connection = pool.acquire().await?;
+        run_plan_with_lock(self, connection, plan).await
+    }
+
+    /// Dry run provided plan migrations
+    ///
+    /// Returns statements which would be executed by each migration of plan
+    /// without making any change to database. If database supports
+    /// transactional DDL, operations of atomic migrations are also executed
+    /// inside a transaction which is always rolled back so failing operations
+    /// can be detected and statements executed by them are recorded. See
+    /// [`DryRunMigration`] for how statements are collected. Similar to
+    /// [`Migrate::run`], if returned future is cancelled lock remains held
+    /// by session of borrowed connection till caller closes it.
+    ///
+    /// # Errors
+    /// If failed to generate plan, failed to execute operations of plan
+    /// migrations or statements of executed operation cannot be captured
+    async fn dry_run(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        plan: &Plan,
+    ) -> Result<Vec<DryRunMigration>, Error> {
+        tracing::debug!("dry running plan {:?}", plan);
+        let transactional_ddl = self.is_transactional_ddl_supported(connection).await?;
+        let mut guard = LockGuard::acquire(
+            self,
+            BorrowedConnection(connection),
+            plan.lock_timeout.or(self.lock_timeout()),
+        )
+        .await?;
+        let connection = &mut *guard;
+        // store result of dry run so that we can unlock lock before returning
+        // result or resuming panic
+        let result = catch_unwind(async {
+            let (migrations, applied_migration_sql_rows) =
+                generate_plan_with_applied_rows(self, connection, Some(plan)).await?;
+            let mut transaction = if transactional_ddl && !plan.fake {
+                Some(connection.begin().await?)
+            } else {
+                None
+            };
+            let recorder = StatementRecorder::new();
+            let mut dry_run_migrations = Vec::with_capacity(migrations.len());
+            // once a non atomic migration is found all later migrations are not
+            // executed since they can depend on changes of non atomic migration
+            let mut executable = transaction.is_some();
+            for migration in migrations {
+                executable = executable && migration.is_atomic();
+                let mut statements = Vec::new();
+                if !plan.fake {
+                    let mut operations = migration
+                        .operations()
+                        .into_iter()
+                        .enumerate()
+                        .collect::<Vec<_>>();
+                    // only remaining operations of partially applied migration are applied
+                    // and only its applied operations are reverted
+                    if let Some(applied_operations) = applied_migration_sql_rows
+                        .iter()
+                        .find(|applied_migration| *applied_migration == migration)
+                        .and_then(AppliedMigrationSqlRow::applied_operations)
+                    {
+                        match plan.plan_type {
+                            PlanType::Apply => {
+                                operations.drain(..applied_operations.min(operations.len()));
                             }
-                            self.add_migration_to_db_table(connection, migration)
-                                .await?;
+                            PlanType::Revert => operations.truncate(applied_operations),
                         }
                     }
-                    PlanType::Revert => {
-                        tracing::debug!("reverting {} : {}", migration.app(), migration.name());
-
-                        // Reverse operation since last applied operation need to be reverted first
-                        let mut operations = migration.operations();
+                    if let PlanType::Revert = plan.plan_type {
+                        // Reverse operation since last applied operation need to be reverted
+                        // first
                         operations.reverse();
-
-                        if migration.is_atomic() {
-                            let mut transaction = connection.begin().await?;
-                            if !plan.fake {
-                                for operation in operations {
-                                    operation.down(&mut transaction).await?;
-                                }
+                    }
+                    let direction = plan.direction();
+                    for (operation_index, operation) in operations {
+                        if executable && let Some(transaction) = transaction.as_mut() {
+                            run_operation(
+                                self,
+                                transaction,
+                                migration,
+                                operation.as_ref(),
+                                operation_index,
+                                direction,
+                                Some(&recorder),
+                            )
+                            .await
+                            .map_err(operation_failed(
+                                migration,
+                                operation_index,
+                                direction,
+                                true,
+                            ))?;
+                        }
+                        // statements recorded while executing operation are
+                        // used if available since static statements of
+                        // operation can differ from what is executed
+                        let recorded = recorder.take();
+                        if recorded.is_empty() {
+                            let statement = match direction {
+                                Direction::Apply => operation.up_sql(),
+                                Direction::Revert => operation.down_sql(),
+                            };
+                            // executed operation without any captured statement
+                            // would silently report empty dry run
+                            if statement.is_none() && executable {
+                                return Err(Error::DryRunStatementsNotCaptured {
+                                    app: migration.app().to_string(),
+                                    name: migration.name().to_string(),
+                                    operation_index,
+                                });
                             }
-                            self.delete_migration_from_db_table(&mut transaction, migration)
-                                .await?;
-                            transaction.commit().await?;
+                            statements.push(statement);
                         } else {
-                            if !plan.fake {
-                                for operation in operations {
-                                    operation.down(connection).await?;
-                                }
-                            }
-                            self.delete_migration_from_db_table(connection, migration)
-                                .await?;
+                            statements.extend(recorded.into_iter().map(Some));
                         }
                     }
                 }
+                dry_run_migrations.push(DryRunMigration {
+                    app: migration.app().to_string(),
+                    name: migration.name().to_string(),
+                    executed: executable,
+                    statements,
+                });
             }
-            Ok(())
-        }
+            if let Some(transaction) = transaction {
+                transaction.rollback().await?;
+            }
+            Ok(dry_run_migrations)
+        })
         .await;
-        // unlock lock before returning result of applying migration
-        self.unlock(connection).await?;
-        result
+        // unlock lock before returning result of dry run
+        guard.release_unwind(result).await
     }
 }
 
 const DEFAULT_TABLE_NAME: &str = "_sqlx_migrator_migrations";
 
+const DEFAULT_LOCK_EXPIRY: Duration = Duration::from_mins(10);
+
 /// A struct that stores migration-related metadata, including the list of
 /// migrations and configuration such as table and schema name
 pub struct Migrator<DB> {
     migrations: Vec<BoxMigration<DB>>,
     table_prefix: Option<String>,
     schema: Option<String>,
+    applied_by: Option<String>,
+    lock_timeout: Option<Duration>,
+    lock_expiry: Duration,
+    #[cfg(feature = "sqlite")]
+    lock_token: u64,
+    retry_policy: RetryPolicy,
+    listeners: Vec<Box<dyn MigrationListener<DB>>>,
 }
 
 impl<DB> Migrator<DB> {
@@ -1031,6 +2482,14 @@ impl<DB> Migrator<DB> {
             migrations: Vec::default(),
             table_prefix: None,
             schema: None,
+            applied_by: None,
+            lock_timeout: None,
+            lock_expiry: DEFAULT_LOCK_EXPIRY,
+            // random token which identifies lock held by migrator
+            #[cfg(feature = "sqlite")]
+            lock_token: RandomState::new().build_hasher().finish(),
+            retry_policy: RetryPolicy::default(),
+            listeners: Vec::new(),
         }
     }
 
@@ -1112,6 +2571,145 @@ impl<DB> Migrator<DB> {
         Ok(self)
     }
 
+    /// Configures the value stored as applied by for migrations applied using
+    /// migrator.
+    ///
+    /// By default, name of current user and host is used in format
+    /// `{user}@{host}`
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[cfg(feature="sqlite")]
+    /// # fn main() {
+    /// let migrator = sqlx_migrator::Migrator::<sqlx::Sqlite>::new().set_applied_by("deploy-bot");
+    /// assert_eq!(migrator.applied_by(), "deploy-bot");
+    /// # }
+    /// # #[cfg(not(feature="sqlite"))]
+    /// # fn main() {}
+    /// ```
+    #[must_use]
+    pub fn set_applied_by(mut self, applied_by: impl Into<String>) -> Self {
+        self.applied_by = Some(applied_by.into());
+        self
+    }
+
+    /// Configures timeout for acquiring migration lock while running plan.
+    ///
+    /// By default, migrator waits forever for lock held by other migrator.
+    /// When timeout is set, [`Error::LockTimeout`] is returned if lock cannot
+    /// be acquired within timeout. Timeout can be overridden for single run
+    /// using [`Plan::lock_timeout`]
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[cfg(feature="sqlite")]
+    /// # fn main() {
+    /// use std::time::Duration;
+    ///
+    /// use sqlx_migrator::{Migrate as _, Migrator};
+    ///
+    /// let migrator = Migrator::<sqlx::Sqlite>::new().set_lock_timeout(Duration::from_secs(30));
+    /// assert_eq!(migrator.lock_timeout(), Some(Duration::from_secs(30)));
+    /// # }
+    /// # #[cfg(not(feature="sqlite"))]
+    /// # fn main() {}
+    /// ```
+    #[must_use]
+    pub fn set_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = Some(lock_timeout);
+        self
+    }
+
+    /// Configures expiry of migration lock which is stored in table.
+    ///
+    /// Sqlite lock is stored as row of lock table which is not removed when
+    /// migrator crashes so lock which is not refreshed within expiry is
+    /// considered stale and it is taken over by other migrator. Lock is
+    /// refreshed before running each migration so expiry should be longer than
+    /// time taken by longest migration. Lock cannot be refreshed while single
+    /// operation is running, so if operation runs longer than expiry other
+    /// migrator can take over lock and run migrations concurrently, refreshing
+    /// lock afterwards fails with [`Error::LockLost`]. By default, lock
+    /// expires after 10 minutes. Advisory lock of postgres and mysql is
+    /// released when session is ended so it do not use expiry
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[cfg(feature="sqlite")]
+    /// # fn main() {
+    /// use std::time::Duration;
+    ///
+    /// use sqlx_migrator::Migrator;
+    ///
+    /// let migrator = Migrator::<sqlx::Sqlite>::new().set_lock_expiry(Duration::from_secs(3600));
+    /// assert_eq!(migrator.lock_expiry(), Duration::from_secs(3600));
+    /// # }
+    /// # #[cfg(not(feature="sqlite"))]
+    /// # fn main() {}
+    /// ```
+    #[must_use]
+    pub fn set_lock_expiry(mut self, lock_expiry: Duration) -> Self {
+        self.lock_expiry = lock_expiry;
+        self
+    }
+
+    /// Adds listener which is notified while running plan using
+    /// [`Migrate::run`]. Listeners are notified in order in which they are
+    /// added
+    #[must_use]
+    pub fn add_listener(mut self, listener: Box<dyn MigrationListener<DB>>) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Configures policy used for retrying atomic migration which failed with
+    /// transient error such as deadlock.
+    ///
+    /// By default, migration is not retried
+    ///
+    /// # Example
+    /// ```rust
+    /// # #[cfg(feature="sqlite")]
+    /// # fn main() {
+    /// use std::time::Duration;
+    ///
+    /// use sqlx_migrator::Migrator;
+    /// use sqlx_migrator::migrator::RetryPolicy;
+    ///
+    /// let migrator = Migrator::<sqlx::Sqlite>::new()
+    ///     .set_retry_policy(RetryPolicy::new(3).backoff(Duration::from_millis(200)));
+    /// # }
+    /// # #[cfg(not(feature="sqlite"))]
+    /// # fn main() {}
+    /// ```
+    #[must_use]
+    pub fn set_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Get expiry of migration lock stored in table
+    #[must_use]
+    pub fn lock_expiry(&self) -> Duration {
+        self.lock_expiry
+    }
+
+    /// Get value which is stored as applied by for migrations applied using
+    /// migrator
+    #[must_use]
+    pub fn applied_by(&self) -> &str {
+        // user and host are only looked up once since they do not change while
+        // process is running
+        static DEFAULT_APPLIED_BY: OnceLock<String> = OnceLock::new();
+        if let Some(applied_by) = &self.applied_by {
+            return applied_by;
+        }
+        DEFAULT_APPLIED_BY.get_or_init(|| {
+            let host = whoami::fallible::hostname().unwrap_or_else(|_| "unknown".to_string());
+            format!("{}@{host}", whoami::username())
+        })
+    }
+
     /// Get name of table which is used for storing migrations related
     /// information in database
     ///
@@ -1149,6 +2747,30 @@ impl<DB> Migrator<DB> {
         }
         table_name
     }
+
+    /// Get name of table which is used for storing history of migration events
+    /// in database. History table name is migrator table name suffixed with
+    /// `_history`
+    ///
+    /// # Examples
+    /// ```rust
+    /// # #[cfg(feature="sqlite")]
+    /// # fn main() {
+    /// let migrator = sqlx_migrator::Migrator::<sqlx::Sqlite>::new()
+    ///     .set_table_prefix("v1")
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     &migrator.history_table_name(),
+    ///     "_v1_sqlx_migrator_migrations_history"
+    /// );
+    /// # }
+    /// # #[cfg(not(feature="sqlite"))]
+    /// # fn main() {}
+    /// ```
+    #[must_use]
+    pub fn history_table_name(&self) -> String {
+        format!("{}_history", self.table_name())
+    }
 }
 
 impl<DB> Default for Migrator<DB> {
@@ -1172,4 +2794,15 @@ where
     DB: Database,
     Self: DatabaseOperation<DB>,
 {
+    fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy.clone()
+    }
+
+    fn lock_timeout(&self) -> Option<Duration> {
+        self.lock_timeout
+    }
+
+    fn listeners(&self) -> &[Box<dyn MigrationListener<DB>>] {
+        &self.listeners
+    }
 }
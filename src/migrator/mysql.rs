@@ -1,8 +1,11 @@
-use sqlx::{Database, MySql};
+use std::time::Duration;
 
-use super::{DatabaseOperation, Migrator};
+use sqlx::mysql::MySqlDatabaseError;
+use sqlx::{Database, Encode, Executor, FromRow, IntoArguments, MySql, Type};
+
+use super::{DatabaseOperation, LockHolder, Migrator, TransientError, add_missing_columns};
 use crate::error::Error;
-use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::migration::{AppliedMigrationSqlRow, HistoryEvent, Migration, MigrationHistorySqlRow};
 
 /// create migrator table query
 #[must_use]
@@ -13,11 +16,41 @@ pub(crate) fn create_migrator_table_query(table_name: &str) -> String {
         app VARCHAR(384) NOT NULL,
         name VARCHAR(384) NOT NULL,
         applied_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        checksum VARCHAR(64),
+        duration_ms BIGINT,
+        applied_by VARCHAR(384),
+        applied_operations BIGINT,
         UNIQUE (app, name)
     )"
     )
 }
 
+/// Columns added to migrator table after its initial version along with
+/// their definition
+pub(crate) fn migrator_table_upgrade_columns() -> &'static [(&'static str, &'static str)] {
+    &[
+        ("checksum", "checksum VARCHAR(64)"),
+        ("duration_ms", "duration_ms BIGINT"),
+        ("applied_by", "applied_by VARCHAR(384)"),
+        ("applied_operations", "applied_operations BIGINT"),
+    ]
+}
+
+/// Create history table query
+#[must_use]
+pub(crate) fn create_history_table_query(table_name: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {table_name} (
+        id INT PRIMARY KEY NOT NULL AUTO_INCREMENT,
+        app VARCHAR(384) NOT NULL,
+        name VARCHAR(384) NOT NULL,
+        event VARCHAR(32) NOT NULL,
+        event_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        performed_by VARCHAR(384)
+    )"
+    )
+}
+
 /// Drop table query
 #[must_use]
 pub(crate) fn drop_table_query(table_name: &str) -> String {
@@ -27,15 +60,51 @@ pub(crate) fn drop_table_query(table_name: &str) -> String {
 /// fetch rows
 pub(crate) fn fetch_rows_query(table_name: &str) -> String {
     format!(
-        "SELECT id, app, name, DATE_FORMAT(applied_time, '%Y-%m-%d %H:%i:%s') AS applied_time \
-         FROM {table_name}"
+        "SELECT id, app, name, DATE_FORMAT(applied_time, '%Y-%m-%d %H:%i:%s') AS applied_time, \
+         checksum, duration_ms, applied_by, applied_operations FROM {table_name}"
     )
 }
 
 /// add migration query
 #[must_use]
 pub(crate) fn add_migration_query(table_name: &str) -> String {
-    format!("INSERT INTO {table_name}(app, name) VALUES (?, ?)")
+    format!("INSERT INTO {table_name}(app, name, checksum, applied_by) VALUES (?, ?, ?, ?)")
+}
+
+/// Add migration with applied time query
+#[must_use]
+pub(crate) fn add_migration_with_applied_time_query(table_name: &str) -> String {
+    format!(
+        "INSERT INTO {table_name}(app, name, checksum, applied_by, applied_time) VALUES (?, ?, ?, \
+         ?, ?)"
+    )
+}
+
+/// Add migration with duration query
+#[must_use]
+pub(crate) fn add_migration_with_duration_query(table_name: &str) -> String {
+    format!(
+        "INSERT INTO {table_name}(app, name, checksum, applied_by, duration_ms) VALUES (?, ?, ?, \
+         ?, ?)"
+    )
+}
+
+/// Update migration duration query
+#[must_use]
+pub(crate) fn update_duration_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET duration_ms = ? WHERE app = ? AND name = ?")
+}
+
+/// Update number of applied operations of partially applied migration query
+#[must_use]
+pub(crate) fn update_applied_operations_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET applied_operations = ? WHERE app = ? AND name = ?")
+}
+
+/// Update checksum of applied migration query
+#[must_use]
+pub(crate) fn update_checksum_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET checksum = ? WHERE app = ? AND name = ?")
 }
 
 /// delete migration query
@@ -44,6 +113,20 @@ pub(crate) fn delete_migration_query(table_name: &str) -> String {
     format!("DELETE FROM {table_name} WHERE app = ? AND name = ?")
 }
 
+/// Fetch history rows query
+pub(crate) fn fetch_history_rows_query(table_name: &str) -> String {
+    format!(
+        "SELECT id, app, name, event, DATE_FORMAT(event_time, '%Y-%m-%d %H:%i:%s') AS event_time, \
+         performed_by FROM {table_name} ORDER BY id"
+    )
+}
+
+/// Add history query
+#[must_use]
+pub(crate) fn add_history_query(table_name: &str) -> String {
+    format!("INSERT INTO {table_name}(app, name, event, performed_by) VALUES (?, ?, ?, ?)")
+}
+
 /// get current database query
 pub(crate) fn current_database_query() -> &'static str {
     "SELECT DATABASE()"
@@ -56,6 +139,19 @@ pub(crate) fn lock_database_query() -> &'static str {
     "SELECT GET_LOCK(?, -1)"
 }
 
+/// get lock database query which waits for lock till provided seconds. Query
+/// returns 1 if lock is acquired
+pub(crate) fn lock_database_with_timeout_query() -> &'static str {
+    "SELECT CAST(GET_LOCK(?, ?) AS SIGNED)"
+}
+
+/// convert lock timeout to whole seconds used by `GET_LOCK`. Partial second is
+/// rounded up so lock is never waited for less than timeout
+pub(crate) fn lock_timeout_secs(timeout: Duration) -> i64 {
+    let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+    i64::try_from(secs).unwrap_or(i64::MAX)
+}
+
 /// get lock database query
 /// # Errors
 /// Failed to lock database
@@ -69,13 +165,185 @@ pub(crate) fn get_lock_id(database_name: &str, table_name: &str) -> String {
     crc32fast::hash(buf.as_bytes()).to_string()
 }
 
+/// get query which returns connection id of session holding lock along with
+/// connection id of current session
+pub(crate) fn lock_holder_query() -> &'static str {
+    "SELECT CAST(IS_USED_LOCK(?) AS SIGNED), CAST(CONNECTION_ID() AS SIGNED)"
+}
+
+/// get query which returns connection id of session holding lock along with
+/// time since when session is in its current state. Mysql do not record time
+/// when named lock is acquired so time is computed from `TIME` column of
+/// processlist which is `NULL` if processlist of other user cannot be read
+pub(crate) fn lock_holder_since_query() -> &'static str {
+    "SELECT CAST(IS_USED_LOCK(?) AS SIGNED), (SELECT DATE_FORMAT(NOW() - INTERVAL p.TIME SECOND, \
+     '%Y-%m-%d %H:%i:%s') FROM information_schema.PROCESSLIST AS p WHERE p.ID = IS_USED_LOCK(?))"
+}
+
+/// get kill connection query. Connection id is formatted in query since it is
+/// integer
+pub(crate) fn kill_connection_query(connection_id: i64) -> String {
+    format!("KILL {connection_id}")
+}
+
+/// get settings along with their value which enforce timeout of operation.
+/// `max_execution_time` uses milliseconds and only applies to read only
+/// `SELECT` statement while `lock_wait_timeout` uses whole seconds
+pub(crate) fn operation_timeout_settings(timeout: Duration) -> Vec<(String, String)> {
+    vec![
+        (
+            "max_execution_time".to_string(),
+            timeout.as_millis().max(1).to_string(),
+        ),
+        (
+            "lock_wait_timeout".to_string(),
+            lock_timeout_secs(timeout).max(1).to_string(),
+        ),
+    ]
+}
+
+/// get query which returns current value of session variable. Variable name
+/// is formatted in query since it cannot be bound
+pub(crate) fn fetch_setting_query(name: &str) -> String {
+    format!("SELECT CAST(@@SESSION.{name} AS CHAR)")
+}
+
+/// get query which sets value of session variable. Variable name is formatted
+/// in query since it cannot be bound
+pub(crate) fn update_setting_query(name: &str) -> String {
+    format!("SET SESSION {name} = CAST(? AS UNSIGNED)")
+}
+
+/// Returns true if error is raised because statement or lock wait exceeded
+/// its timeout
+pub(crate) fn is_timeout_error(error: &Error) -> bool {
+    let Error::Sqlx(sqlx::Error::Database(database_error)) = error else {
+        return false;
+    };
+    // 1205 is lock wait timeout exceeded and 3024 is maximum statement
+    // execution time exceeded
+    database_error
+        .try_downcast_ref::<MySqlDatabaseError>()
+        .is_some_and(|mysql_error| matches!(mysql_error.number(), 1205 | 3024))
+}
+
+/// Returns class of transient error if error is raised due to concurrent
+/// transaction
+pub(crate) fn transient_error(error: &Error) -> Option<TransientError> {
+    let Error::Sqlx(sqlx::Error::Database(database_error)) = error else {
+        return None;
+    };
+    match database_error
+        .try_downcast_ref::<MySqlDatabaseError>()?
+        .number()
+    {
+        1205 => Some(TransientError::LockTimeout),
+        1213 => Some(TransientError::Deadlock),
+        _ => None,
+    }
+}
+
+/// Update session variables and return name and previous value of variables
+pub(crate) async fn replace_settings<DB>(
+    connection: &mut <DB as Database>::Connection,
+    settings: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (String,): FromRow<'r, <DB as Database>::Row>,
+{
+    let mut previous_settings = Vec::with_capacity(settings.len());
+    for (name, value) in settings {
+        let (previous_value,) = sqlx::query_as::<DB, (String,)>(&fetch_setting_query(&name))
+            .fetch_one(&mut *connection)
+            .await?;
+        sqlx::query::<DB>(&update_setting_query(&name))
+            .bind(value)
+            .execute(&mut *connection)
+            .await?;
+        previous_settings.push((name, previous_value));
+    }
+    Ok(previous_settings)
+}
+
+/// Fetch session holding named lock
+pub(crate) async fn fetch_lock_holder<DB>(
+    connection: &mut <DB as Database>::Connection,
+    lock_id: String,
+) -> Result<Option<LockHolder>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (Option<i64>, Option<String>): FromRow<'r, <DB as Database>::Row>,
+{
+    let (holder, since): (Option<i64>, Option<String>) =
+        sqlx::query_as::<DB, _>(lock_holder_since_query())
+            .bind(lock_id.clone())
+            .bind(lock_id)
+            .fetch_one(connection)
+            .await?;
+    Ok(holder.map(|connection_id| LockHolder::new(connection_id.to_string(), since)))
+}
+
+/// Release named lock if it is held by provided session. Lock held by other
+/// session is released by killing that session
+pub(crate) async fn force_unlock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    lock_id: String,
+    session: &str,
+) -> Result<bool, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (Option<i64>, i64): FromRow<'r, <DB as Database>::Row>,
+{
+    let (holder, current): (Option<i64>, i64) = sqlx::query_as::<DB, _>(lock_holder_query())
+        .bind(lock_id.clone())
+        .fetch_one(&mut *connection)
+        .await?;
+    match holder {
+        Some(holder) if holder.to_string() != session => Ok(false),
+        None => Ok(false),
+        Some(holder) if holder == current => {
+            sqlx::query::<DB>(unlock_database_query())
+                .bind(lock_id)
+                .execute(connection)
+                .await?;
+            Ok(true)
+        }
+        Some(holder) => {
+            sqlx::query::<DB>(&kill_connection_query(holder))
+                .execute(connection)
+                .await?;
+            Ok(true)
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl DatabaseOperation<MySql> for Migrator<MySql> {
     async fn ensure_migration_table_exists(
         &self,
         connection: &mut <MySql as Database>::Connection,
     ) -> Result<(), Error> {
-        sqlx::query(&create_migrator_table_query(&self.table_name()))
+        let table_name = self.table_name();
+        sqlx::query(&create_migrator_table_query(&table_name))
+            .execute(&mut *connection)
+            .await?;
+        add_missing_columns::<MySql>(
+            &mut *connection,
+            &table_name,
+            migrator_table_upgrade_columns(),
+        )
+        .await?;
+        sqlx::query(&create_history_table_query(&self.history_table_name()))
             .execute(connection)
             .await?;
         Ok(())
@@ -97,6 +365,40 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
         migration: &Box<dyn Migration<MySql>>,
     ) -> Result<(), Error> {
         sqlx::query(&add_migration_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table_with_duration(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        migration: &Box<dyn Migration<MySql>>,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_with_duration_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_duration(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        migration: &Box<dyn Migration<MySql>>,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_duration_query(&self.table_name()))
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
             .bind(migration.app())
             .bind(migration.name())
             .execute(connection)
@@ -104,6 +406,52 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
         Ok(())
     }
 
+    async fn update_applied_operations(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        migration: &Box<dyn Migration<MySql>>,
+        applied_operations: Option<usize>,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_applied_operations_query(&self.table_name()))
+            .bind(applied_operations.map(|count| i64::try_from(count).unwrap_or(i64::MAX)))
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_checksum(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        migration: &Box<dyn Migration<MySql>>,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_checksum_query(&self.table_name()))
+            .bind(migration.checksum())
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        migration: &Box<dyn Migration<MySql>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_with_applied_time_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(applied_time)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
     async fn delete_migration_from_db_table(
         &self,
         connection: &mut <MySql as Database>::Connection,
@@ -128,6 +476,71 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
         )
     }
 
+    async fn add_history_to_db_table(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        migration: &Box<dyn Migration<MySql>>,
+        event: HistoryEvent,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_history_query(&self.history_table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(event.as_str())
+            .bind(self.applied_by())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_history_from_db(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+    ) -> Result<Vec<MigrationHistorySqlRow>, Error> {
+        Ok(
+            sqlx::query_as::<_, MigrationHistorySqlRow>(&fetch_history_rows_query(
+                &self.history_table_name(),
+            ))
+            .fetch_all(connection)
+            .await?,
+        )
+    }
+
+    async fn is_transactional_ddl_supported(
+        &self,
+        _connection: &mut <MySql as Database>::Connection,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn is_operation_progress_supported(&self) -> bool {
+        true
+    }
+
+    async fn set_operation_timeout(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<Vec<(String, String)>, Error> {
+        replace_settings::<MySql>(connection, operation_timeout_settings(timeout)).await
+    }
+
+    async fn restore_operation_timeout(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        settings: Vec<(String, String)>,
+    ) -> Result<(), Error> {
+        replace_settings::<MySql>(connection, settings).await?;
+        Ok(())
+    }
+
+    fn is_timeout_error(&self, error: &Error) -> bool {
+        is_timeout_error(error)
+    }
+
+    fn transient_error(&self, error: &Error) -> Option<TransientError> {
+        transient_error(error)
+    }
+
     async fn lock(&self, connection: &mut <MySql as Database>::Connection) -> Result<(), Error> {
         let (database_name,): (String,) = sqlx::query_as(current_database_query())
             .fetch_one(&mut *connection)
@@ -140,6 +553,26 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
         Ok(())
     }
 
+    async fn lock_with_timeout(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let (database_name,): (String,) = sqlx::query_as(current_database_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, &self.table_name());
+        let (locked,): (Option<i64>,) = sqlx::query_as(lock_database_with_timeout_query())
+            .bind(lock_id)
+            .bind(lock_timeout_secs(timeout))
+            .fetch_one(connection)
+            .await?;
+        if locked != Some(1) {
+            return Err(Error::LockTimeout { timeout });
+        }
+        Ok(())
+    }
+
     async fn unlock(&self, connection: &mut <MySql as Database>::Connection) -> Result<(), Error> {
         let (database_name,): (String,) = sqlx::query_as(current_database_query())
             .fetch_one(&mut *connection)
@@ -151,4 +584,27 @@ impl DatabaseOperation<MySql> for Migrator<MySql> {
             .await?;
         Ok(())
     }
+
+    async fn fetch_lock_holder(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+    ) -> Result<Option<LockHolder>, Error> {
+        let (database_name,): (String,) = sqlx::query_as(current_database_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, &self.table_name());
+        fetch_lock_holder::<MySql>(connection, lock_id).await
+    }
+
+    async fn force_unlock(
+        &self,
+        connection: &mut <MySql as Database>::Connection,
+        session: &str,
+    ) -> Result<bool, Error> {
+        let (database_name,): (String,) = sqlx::query_as(current_database_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, &self.table_name());
+        force_unlock::<MySql>(connection, lock_id, session).await
+    }
 }
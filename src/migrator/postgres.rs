@@ -1,8 +1,11 @@
-use sqlx::{Database, Postgres};
+use std::time::{Duration, Instant};
 
-use super::{DatabaseOperation, Migrator};
+use sqlx::postgres::PgDatabaseError;
+use sqlx::{Database, Encode, Executor, FromRow, IntoArguments, Postgres, Type};
+
+use super::{DatabaseOperation, LockHolder, Migrator, TransientError, add_missing_columns};
 use crate::error::Error;
-use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::migration::{AppliedMigrationSqlRow, HistoryEvent, Migration, MigrationHistorySqlRow};
 
 /// Create migrator table query
 #[must_use]
@@ -13,11 +16,41 @@ pub(crate) fn create_migrator_table_query(table_name: &str) -> String {
         app TEXT NOT NULL,
         name TEXT NOT NULL,
         applied_time TIMESTAMPTZ NOT NULL DEFAULT now(),
+        checksum TEXT,
+        duration_ms BIGINT,
+        applied_by TEXT,
+        applied_operations BIGINT,
         UNIQUE (app, name)
     )"
     )
 }
 
+/// Columns added to migrator table after its initial version along with
+/// their definition
+pub(crate) fn migrator_table_upgrade_columns() -> &'static [(&'static str, &'static str)] {
+    &[
+        ("checksum", "checksum TEXT"),
+        ("duration_ms", "duration_ms BIGINT"),
+        ("applied_by", "applied_by TEXT"),
+        ("applied_operations", "applied_operations BIGINT"),
+    ]
+}
+
+/// Create history table query
+#[must_use]
+pub(crate) fn create_history_table_query(table_name: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {table_name} (
+        id INT PRIMARY KEY NOT NULL GENERATED ALWAYS AS IDENTITY,
+        app TEXT NOT NULL,
+        name TEXT NOT NULL,
+        event TEXT NOT NULL,
+        event_time TIMESTAMPTZ NOT NULL DEFAULT now(),
+        performed_by TEXT
+    )"
+    )
+}
+
 /// Drop table query
 #[must_use]
 pub(crate) fn drop_table_query(table_name: &str) -> String {
@@ -26,13 +59,52 @@ pub(crate) fn drop_table_query(table_name: &str) -> String {
 
 /// Fetch rows
 pub(crate) fn fetch_rows_query(table_name: &str) -> String {
-    format!("SELECT id, app, name, applied_time::TEXT FROM {table_name}")
+    format!(
+        "SELECT id, app, name, applied_time::TEXT, checksum, duration_ms, applied_by, \
+         applied_operations FROM {table_name}"
+    )
 }
 
 /// Add migration query
 #[must_use]
 pub(crate) fn add_migration_query(table_name: &str) -> String {
-    format!("INSERT INTO {table_name}(app, name) VALUES ($1, $2)")
+    format!("INSERT INTO {table_name}(app, name, checksum, applied_by) VALUES ($1, $2, $3, $4)")
+}
+
+/// Add migration with applied time query
+#[must_use]
+pub(crate) fn add_migration_with_applied_time_query(table_name: &str) -> String {
+    format!(
+        "INSERT INTO {table_name}(app, name, checksum, applied_by, applied_time) VALUES ($1, $2, \
+         $3, $4, CAST($5 AS TIMESTAMPTZ))"
+    )
+}
+
+/// Add migration with duration query
+#[must_use]
+pub(crate) fn add_migration_with_duration_query(table_name: &str) -> String {
+    format!(
+        "INSERT INTO {table_name}(app, name, checksum, applied_by, duration_ms) VALUES ($1, $2, \
+         $3, $4, $5)"
+    )
+}
+
+/// Update migration duration query
+#[must_use]
+pub(crate) fn update_duration_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET duration_ms = $1 WHERE app = $2 AND name = $3")
+}
+
+/// Update number of applied operations of partially applied migration query
+#[must_use]
+pub(crate) fn update_applied_operations_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET applied_operations = $1 WHERE app = $2 AND name = $3")
+}
+
+/// Update checksum of applied migration query
+#[must_use]
+pub(crate) fn update_checksum_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET checksum = $1 WHERE app = $2 AND name = $3")
 }
 
 /// Delete migration query
@@ -41,6 +113,19 @@ pub(crate) fn delete_migration_query(table_name: &str) -> String {
     format!("DELETE FROM {table_name} WHERE app = $1 AND name = $2")
 }
 
+/// Fetch history rows query
+pub(crate) fn fetch_history_rows_query(table_name: &str) -> String {
+    format!(
+        "SELECT id, app, name, event, event_time::TEXT, performed_by FROM {table_name} ORDER BY id"
+    )
+}
+
+/// Add history query
+#[must_use]
+pub(crate) fn add_history_query(table_name: &str) -> String {
+    format!("INSERT INTO {table_name}(app, name, event, performed_by) VALUES ($1, $2, $3, $4)")
+}
+
 /// get current database query
 pub(crate) fn current_database_query() -> &'static str {
     "SELECT CURRENT_DATABASE()"
@@ -51,6 +136,19 @@ pub(crate) fn lock_database_query() -> &'static str {
     "SELECT pg_advisory_lock($1)"
 }
 
+/// get try lock database query which returns whether lock is acquired
+fn try_lock_database_query() -> &'static str {
+    "SELECT pg_try_advisory_lock($1)"
+}
+
+/// get query which waits for provided seconds before retrying lock
+fn lock_wait_query() -> &'static str {
+    "SELECT 1 FROM pg_sleep($1)"
+}
+
+/// Interval between attempts of acquiring lock when lock timeout is used
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
 /// get lock database query
 pub(crate) fn unlock_database_query() -> &'static str {
     "SELECT pg_advisory_unlock($1)"
@@ -62,13 +160,195 @@ pub(crate) fn get_lock_id(database_name: &str, table_name: &str) -> i64 {
     i64::from(crc32fast::hash(buf.as_bytes()))
 }
 
+/// get query which returns process id of session holding lock along with
+/// start time of session. Advisory lock with bigint key is stored in `pg_locks`
+/// with high bits in classid and low bits in objid
+pub(crate) fn lock_holder_query() -> &'static str {
+    "SELECT l.pid::TEXT, a.backend_start::TEXT FROM pg_locks AS l LEFT JOIN pg_stat_activity AS a \
+     ON a.pid = l.pid WHERE l.locktype = 'advisory' AND l.granted AND l.objsubid = 1 AND \
+     l.database = (SELECT oid FROM pg_database WHERE datname = CURRENT_DATABASE()) AND \
+     ((l.classid::BIGINT << 32) | l.objid::BIGINT) = $1"
+}
+
+/// get query which releases lock held by current session or terminates other
+/// session holding lock. Only session whose process id is provided is released
+/// and query returns row if that session holds lock
+pub(crate) fn force_unlock_query() -> &'static str {
+    "SELECT CASE WHEN l.pid = pg_backend_pid() THEN pg_advisory_unlock($1) ELSE \
+     pg_terminate_backend(l.pid) END FROM pg_locks AS l WHERE l.locktype = 'advisory' AND \
+     l.granted AND l.objsubid = 1 AND l.database = (SELECT oid FROM pg_database WHERE datname = \
+     CURRENT_DATABASE()) AND ((l.classid::BIGINT << 32) | l.objid::BIGINT) = $1 AND l.pid::TEXT = \
+     $2"
+}
+
+/// get settings along with their value which enforce timeout of operation.
+/// Timeout is rounded to milliseconds but never to zero since zero disables
+/// timeout
+pub(crate) fn operation_timeout_settings(timeout: Duration) -> Vec<(String, String)> {
+    let milliseconds = format!("{}ms", timeout.as_millis().max(1));
+    vec![
+        ("statement_timeout".to_string(), milliseconds.clone()),
+        ("lock_timeout".to_string(), milliseconds),
+    ]
+}
+
+/// get query which returns current value of setting
+pub(crate) fn fetch_setting_query() -> &'static str {
+    "SELECT current_setting($1)"
+}
+
+/// get query which sets value of setting for session
+pub(crate) fn update_setting_query() -> &'static str {
+    "SELECT set_config($1, $2, false)"
+}
+
+/// Returns true if error is raised because statement or lock wait exceeded
+/// its timeout
+pub(crate) fn is_timeout_error(error: &Error) -> bool {
+    let Error::Sqlx(sqlx::Error::Database(database_error)) = error else {
+        return false;
+    };
+    // 57014 is query_canceled and 55P03 is lock_not_available
+    database_error
+        .try_downcast_ref::<PgDatabaseError>()
+        .is_some_and(|pg_error| matches!(pg_error.code(), "57014" | "55P03"))
+}
+
+/// Returns class of transient error if error is raised due to concurrent
+/// transaction
+pub(crate) fn transient_error(error: &Error) -> Option<TransientError> {
+    let Error::Sqlx(sqlx::Error::Database(database_error)) = error else {
+        return None;
+    };
+    match database_error.try_downcast_ref::<PgDatabaseError>()?.code() {
+        "55P03" => Some(TransientError::LockTimeout),
+        "40001" => Some(TransientError::SerializationFailure),
+        "40P01" => Some(TransientError::Deadlock),
+        _ => None,
+    }
+}
+
+/// Try to acquire advisory lock till timeout is elapsed. Lock is retried
+/// after waiting with `pg_sleep` so waiting do not depend upon async runtime
+pub(crate) async fn try_lock_with_timeout<DB>(
+    connection: &mut <DB as Database>::Connection,
+    lock_id: i64,
+    timeout: Duration,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> f64: Encode<'q, DB> + Type<DB>,
+    for<'r> (bool,): FromRow<'r, <DB as Database>::Row>,
+{
+    let start = Instant::now();
+    loop {
+        let (locked,) = sqlx::query_as::<DB, (bool,)>(try_lock_database_query())
+            .bind(lock_id)
+            .fetch_one(&mut *connection)
+            .await?;
+        if locked {
+            return Ok(());
+        }
+        let remaining = timeout.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            return Err(Error::LockTimeout { timeout });
+        }
+        sqlx::query::<DB>(lock_wait_query())
+            .bind(remaining.min(LOCK_RETRY_INTERVAL).as_secs_f64())
+            .execute(&mut *connection)
+            .await?;
+    }
+}
+
+/// Update settings of session and return name and previous value of settings
+pub(crate) async fn replace_settings<DB>(
+    connection: &mut <DB as Database>::Connection,
+    settings: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (String,): FromRow<'r, <DB as Database>::Row>,
+{
+    let mut previous_settings = Vec::with_capacity(settings.len());
+    for (name, value) in settings {
+        let (previous_value,) = sqlx::query_as::<DB, (String,)>(fetch_setting_query())
+            .bind(name.clone())
+            .fetch_one(&mut *connection)
+            .await?;
+        sqlx::query_as::<DB, (String,)>(update_setting_query())
+            .bind(name.clone())
+            .bind(value)
+            .fetch_one(&mut *connection)
+            .await?;
+        previous_settings.push((name, previous_value));
+    }
+    Ok(previous_settings)
+}
+
+/// Fetch session holding advisory lock
+pub(crate) async fn fetch_lock_holder<DB>(
+    connection: &mut <DB as Database>::Connection,
+    lock_id: i64,
+) -> Result<Option<LockHolder>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'r> (String, Option<String>): FromRow<'r, <DB as Database>::Row>,
+{
+    let holder = sqlx::query_as::<DB, (String, Option<String>)>(lock_holder_query())
+        .bind(lock_id)
+        .fetch_optional(connection)
+        .await?;
+    Ok(holder.map(|(pid, backend_start)| LockHolder::new(pid, backend_start)))
+}
+
+/// Release advisory lock if it is held by provided session
+pub(crate) async fn force_unlock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    lock_id: i64,
+    session: &str,
+) -> Result<bool, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (bool,): FromRow<'r, <DB as Database>::Row>,
+{
+    let released = sqlx::query_as::<DB, (bool,)>(force_unlock_query())
+        .bind(lock_id)
+        .bind(session.to_string())
+        .fetch_all(connection)
+        .await?;
+    Ok(released.iter().any(|&(released,)| released))
+}
+
 #[async_trait::async_trait]
 impl DatabaseOperation<Postgres> for Migrator<Postgres> {
     async fn ensure_migration_table_exists(
         &self,
         connection: &mut <Postgres as Database>::Connection,
     ) -> Result<(), Error> {
-        sqlx::query(&create_migrator_table_query(&self.table_name()))
+        let table_name = self.table_name();
+        sqlx::query(&create_migrator_table_query(&table_name))
+            .execute(&mut *connection)
+            .await?;
+        add_missing_columns::<Postgres>(
+            &mut *connection,
+            &table_name,
+            migrator_table_upgrade_columns(),
+        )
+        .await?;
+        sqlx::query(&create_history_table_query(&self.history_table_name()))
             .execute(connection)
             .await?;
         Ok(())
@@ -92,6 +372,86 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
         sqlx::query(&add_migration_query(&self.table_name()))
             .bind(migration.app())
             .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table_with_duration(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        migration: &Box<dyn Migration<Postgres>>,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_with_duration_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_duration(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        migration: &Box<dyn Migration<Postgres>>,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_duration_query(&self.table_name()))
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_applied_operations(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        migration: &Box<dyn Migration<Postgres>>,
+        applied_operations: Option<usize>,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_applied_operations_query(&self.table_name()))
+            .bind(applied_operations.map(|count| i64::try_from(count).unwrap_or(i64::MAX)))
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_checksum(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        migration: &Box<dyn Migration<Postgres>>,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_checksum_query(&self.table_name()))
+            .bind(migration.checksum())
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        migration: &Box<dyn Migration<Postgres>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_with_applied_time_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(applied_time)
             .execute(connection)
             .await?;
         Ok(())
@@ -121,6 +481,71 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
         )
     }
 
+    async fn add_history_to_db_table(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        migration: &Box<dyn Migration<Postgres>>,
+        event: HistoryEvent,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_history_query(&self.history_table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(event.as_str())
+            .bind(self.applied_by())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_history_from_db(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+    ) -> Result<Vec<MigrationHistorySqlRow>, Error> {
+        Ok(
+            sqlx::query_as::<_, MigrationHistorySqlRow>(&fetch_history_rows_query(
+                &self.history_table_name(),
+            ))
+            .fetch_all(connection)
+            .await?,
+        )
+    }
+
+    async fn is_transactional_ddl_supported(
+        &self,
+        _connection: &mut <Postgres as Database>::Connection,
+    ) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn is_operation_progress_supported(&self) -> bool {
+        true
+    }
+
+    async fn set_operation_timeout(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<Vec<(String, String)>, Error> {
+        replace_settings::<Postgres>(connection, operation_timeout_settings(timeout)).await
+    }
+
+    async fn restore_operation_timeout(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        settings: Vec<(String, String)>,
+    ) -> Result<(), Error> {
+        replace_settings::<Postgres>(connection, settings).await?;
+        Ok(())
+    }
+
+    fn is_timeout_error(&self, error: &Error) -> bool {
+        is_timeout_error(error)
+    }
+
+    fn transient_error(&self, error: &Error) -> Option<TransientError> {
+        transient_error(error)
+    }
+
     async fn lock(&self, connection: &mut <Postgres as Database>::Connection) -> Result<(), Error> {
         let (database_name,): (String,) = sqlx::query_as(current_database_query())
             .fetch_one(&mut *connection)
@@ -133,6 +558,18 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
         Ok(())
     }
 
+    async fn lock_with_timeout(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let (database_name,): (String,) = sqlx::query_as(current_database_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, &self.table_name());
+        try_lock_with_timeout::<Postgres>(connection, lock_id, timeout).await
+    }
+
     async fn unlock(
         &self,
         connection: &mut <Postgres as Database>::Connection,
@@ -147,4 +584,27 @@ impl DatabaseOperation<Postgres> for Migrator<Postgres> {
             .await?;
         Ok(())
     }
+
+    async fn fetch_lock_holder(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+    ) -> Result<Option<LockHolder>, Error> {
+        let (database_name,): (String,) = sqlx::query_as(current_database_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, &self.table_name());
+        fetch_lock_holder::<Postgres>(connection, lock_id).await
+    }
+
+    async fn force_unlock(
+        &self,
+        connection: &mut <Postgres as Database>::Connection,
+        session: &str,
+    ) -> Result<bool, Error> {
+        let (database_name,): (String,) = sqlx::query_as(current_database_query())
+            .fetch_one(&mut *connection)
+            .await?;
+        let lock_id = get_lock_id(&database_name, &self.table_name());
+        force_unlock::<Postgres>(connection, lock_id, session).await
+    }
 }
@@ -0,0 +1,154 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tracing::field::{Field, Visit};
+use tracing::instrument::WithSubscriber as _;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+
+/// Target of events emitted by sqlx for every executed statement
+const QUERY_TARGET: &str = "sqlx::query";
+
+/// Records statements executed by sqlx while future is polled
+///
+/// sqlx emits an event for every executed statement when statement logging
+/// is enabled in connect options. Recorder collects these events while still
+/// forwarding every event and span to subscriber which was in use before.
+/// Statements executed by sqlite are not recorded since sqlite runs them
+/// inside its own worker thread
+pub(crate) struct StatementRecorder {
+    statements: Arc<Mutex<Vec<String>>>,
+    dispatch: Dispatch,
+}
+
+impl StatementRecorder {
+    /// Create new recorder which forwards events to current default
+    /// subscriber
+    pub(crate) fn new() -> Self {
+        let statements = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = RecordingSubscriber {
+            statements: Arc::clone(&statements),
+            inner: tracing::dispatcher::get_default(Clone::clone),
+        };
+        Self {
+            statements,
+            dispatch: Dispatch::new(subscriber),
+        }
+    }
+
+    /// Run future while recording statements executed by it
+    pub(crate) async fn record<F>(&self, future: F) -> F::Output
+    where
+        F: Future,
+    {
+        future.with_subscriber(self.dispatch.clone()).await
+    }
+
+    /// Take statements recorded till now
+    pub(crate) fn take(&self) -> Vec<String> {
+        std::mem::take(
+            &mut *self
+                .statements
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+}
+
+/// Subscriber which records statements of sqlx query events and forwards all
+/// other calls to inner dispatch
+struct RecordingSubscriber {
+    statements: Arc<Mutex<Vec<String>>>,
+    inner: Dispatch,
+}
+
+impl Subscriber for RecordingSubscriber {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if metadata.target() == QUERY_TARGET {
+            return Interest::sometimes();
+        }
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == QUERY_TARGET || self.inner.enabled(metadata)
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        None
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.inner.new_span(span)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        self.inner.record(span, values);
+    }
+
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.inner.record_follows_from(span, follows);
+    }
+
+    fn event(&self, event: &Event<'_>) {
+        if event.metadata().target() == QUERY_TARGET {
+            let mut visitor = StatementVisitor::default();
+            event.record(&mut visitor);
+            if let Some(statement) = visitor.statement() {
+                self.statements
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(statement);
+            }
+        }
+        if self.inner.enabled(event.metadata()) {
+            self.inner.event(event);
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        self.inner.enter(span);
+    }
+
+    fn exit(&self, span: &Id) {
+        self.inner.exit(span);
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        self.inner.clone_span(id)
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        self.inner.try_close(id)
+    }
+}
+
+/// Visitor which reads statement from fields of sqlx query event. sqlx only
+/// logs full statement when it is longer than summary
+#[derive(Default)]
+struct StatementVisitor {
+    summary: Option<String>,
+    statement: Option<String>,
+}
+
+impl StatementVisitor {
+    fn statement(self) -> Option<String> {
+        self.statement
+            .map(|statement| statement.trim().to_string())
+            .filter(|statement| !statement.is_empty())
+            .or(self.summary)
+    }
+}
+
+impl Visit for StatementVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = Some(value.to_string()),
+            "db.statement" => self.statement = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn Debug) {}
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use sqlx::Database;
+
+use super::DatabaseOperation;
+use crate::error::Error;
+
+/// Class of transient database error which can succeed when retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TransientError {
+    /// Waiting for lock held by other session exceeded timeout of database.
+    /// Postgres `55P03`, mysql `1205` and sqlite `SQLITE_BUSY` errors
+    LockTimeout,
+    /// Transaction cannot be serialized with concurrent transaction. Postgres
+    /// `40001` error
+    SerializationFailure,
+    /// Transaction is chosen as victim of deadlock. Postgres `40P01` and mysql
+    /// `1213` errors
+    Deadlock,
+}
+
+/// Policy used for retrying atomic migration which failed with transient
+/// error
+///
+/// Whole transaction of atomic migration is retried till attempts are
+/// exhausted. Delay between attempts starts from backoff and is doubled after
+/// each attempt till max backoff. Non atomic migration is never retried since
+/// its operations which are run before failure remain applied.
+///
+/// When database do not support transactional DDL, such as mysql, transaction
+/// of atomic migration is still rolled back by deadlock or lock wait timeout
+/// and migration is retried. DDL statement commits transaction implicitly so
+/// DDL statements run before failure remain applied and are run again by next
+/// attempt. Retry should only be enabled for such database when statements of
+/// migration can be run again, such as migration which only changes data.
+///
+/// Operation which exceeds its own timeout fails with
+/// [`Error::OperationTimeout`](crate::error::Error::OperationTimeout) which is
+/// not retried.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+///
+/// use sqlx_migrator::migrator::{RetryPolicy, TransientError};
+///
+/// let policy = RetryPolicy::new(3)
+///     .backoff(Duration::from_millis(50))
+///     .retry_on(vec![TransientError::Deadlock]);
+/// assert_eq!(policy.max_attempts(), 3);
+/// assert_eq!(policy.delay(2), Duration::from_millis(50));
+/// assert_eq!(policy.delay(3), Duration::from_millis(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    backoff: Duration,
+    max_backoff: Duration,
+    retry_on: Vec<TransientError>,
+}
+
+impl RetryPolicy {
+    /// Create new retry policy which runs migration at most provided number of
+    /// times. By default, backoff is 100 milliseconds, max backoff is 5
+    /// seconds and all transient errors are retried
+    #[must_use]
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            retry_on: vec![
+                TransientError::LockTimeout,
+                TransientError::SerializationFailure,
+                TransientError::Deadlock,
+            ],
+        }
+    }
+
+    /// Set delay before first retry
+    #[must_use]
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set maximum delay between two attempts
+    #[must_use]
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set transient errors which are retried
+    #[must_use]
+    pub fn retry_on(mut self, retry_on: Vec<TransientError>) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Get maximum number of times migration is run
+    #[must_use]
+    pub fn max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Get transient errors which are retried
+    #[must_use]
+    pub fn retried_errors(&self) -> &[TransientError] {
+        &self.retry_on
+    }
+
+    /// Get delay before running provided attempt. Attempt is one based so
+    /// delay before second attempt is backoff
+    #[must_use]
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let exponent = u32::try_from(attempt.saturating_sub(2)).unwrap_or(u32::MAX);
+        let multiplier = 2_u32.checked_pow(exponent).unwrap_or(u32::MAX);
+        self.backoff
+            .checked_mul(multiplier)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Returns delay before next attempt if error raised by provided attempt
+    /// is transient error which is retried and attempts are not exhausted
+    pub(crate) fn retry_delay<DB, M>(
+        &self,
+        migrator: &M,
+        attempt: usize,
+        error: &Error,
+    ) -> Option<Duration>
+    where
+        DB: Database,
+        M: DatabaseOperation<DB> + ?Sized,
+    {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let mut error = error;
+        while let Error::OperationFailed { source, .. } = error {
+            error = source;
+        }
+        let transient_error = migrator.transient_error(error)?;
+        self.retry_on
+            .contains(&transient_error)
+            .then(|| self.delay(attempt + 1))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Policy which do not retry migration
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
@@ -1,8 +1,12 @@
-use sqlx::{Database, Sqlite};
+use std::time::{Duration, Instant};
 
-use super::{DatabaseOperation, Migrator};
+use sqlx::error::DatabaseError as _;
+use sqlx::sqlite::SqliteError;
+use sqlx::{Connection as _, Database, Encode, Executor, FromRow, IntoArguments, Sqlite, Type};
+
+use super::{DatabaseOperation, LockHolder, Migrator, TransientError, add_missing_columns};
 use crate::error::Error;
-use crate::migration::{AppliedMigrationSqlRow, Migration};
+use crate::migration::{AppliedMigrationSqlRow, HistoryEvent, Migration, MigrationHistorySqlRow};
 
 /// create migrator table
 #[must_use]
@@ -13,11 +17,459 @@ pub(crate) fn create_migrator_table_query(table_name: &str) -> String {
         app TEXT NOT NULL,
         name TEXT NOT NULL,
         applied_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        checksum TEXT,
+        duration_ms INTEGER,
+        applied_by TEXT,
+        applied_operations INTEGER,
         UNIQUE (app, name)
     )"
     )
 }
 
+/// Columns added to migrator table after its initial version along with
+/// their definition
+pub(crate) fn migrator_table_upgrade_columns() -> &'static [(&'static str, &'static str)] {
+    &[
+        ("checksum", "checksum TEXT"),
+        ("duration_ms", "duration_ms INTEGER"),
+        ("applied_by", "applied_by TEXT"),
+        ("applied_operations", "applied_operations INTEGER"),
+    ]
+}
+
+/// Create history table query
+#[must_use]
+pub(crate) fn create_history_table_query(table_name: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {table_name} (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        app TEXT NOT NULL,
+        name TEXT NOT NULL,
+        event TEXT NOT NULL,
+        event_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        performed_by TEXT
+    )"
+    )
+}
+
+/// Get name of table which is used for locking database
+#[must_use]
+pub(crate) fn lock_table_name(table_name: &str) -> String {
+    format!("{table_name}_lock")
+}
+
+/// Create lock table query. Table can only contain single row which is present
+/// while lock is held
+#[must_use]
+pub(crate) fn create_lock_table_query(table_name: &str) -> String {
+    let lock_table_name = lock_table_name(table_name);
+    format!(
+        "CREATE TABLE IF NOT EXISTS {lock_table_name} (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        owner TEXT NOT NULL,
+        locked_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )"
+    )
+}
+
+/// Acquire lock query. Lock is acquired if query returns inserted row
+#[must_use]
+pub(crate) fn acquire_lock_query(table_name: &str) -> String {
+    let lock_table_name = lock_table_name(table_name);
+    format!("INSERT OR IGNORE INTO {lock_table_name}(id, owner) VALUES (1, $1) RETURNING id")
+}
+
+/// Release lock query
+#[must_use]
+pub(crate) fn release_lock_query(table_name: &str) -> String {
+    let lock_table_name = lock_table_name(table_name);
+    format!("DELETE FROM {lock_table_name} WHERE id = 1 AND owner = $1")
+}
+
+/// Release stale lock query. Lock is stale if its owner has not refreshed lock
+/// since expiry. Stale lock was present if query returns deleted row
+#[must_use]
+pub(crate) fn release_stale_lock_query(table_name: &str) -> String {
+    let lock_table_name = lock_table_name(table_name);
+    format!(
+        "DELETE FROM {lock_table_name} WHERE id = 1 AND locked_at < datetime('now', $1) RETURNING \
+         owner"
+    )
+}
+
+/// Refresh lock query. Lock is still held by owner if query returns updated
+/// row
+#[must_use]
+pub(crate) fn refresh_lock_query(table_name: &str) -> String {
+    let lock_table_name = lock_table_name(table_name);
+    format!(
+        "UPDATE {lock_table_name} SET locked_at = CURRENT_TIMESTAMP WHERE id = 1 AND owner = $1 \
+         RETURNING id"
+    )
+}
+
+/// Fetch lock holder query
+#[must_use]
+pub(crate) fn lock_holder_query(table_name: &str) -> String {
+    let lock_table_name = lock_table_name(table_name);
+    format!("SELECT owner, CAST(locked_at AS TEXT) FROM {lock_table_name} WHERE id = 1")
+}
+
+/// Force release lock held by provided owner query. Lock was held by owner if
+/// query returns deleted row
+#[must_use]
+pub(crate) fn force_release_lock_query(table_name: &str) -> String {
+    let lock_table_name = lock_table_name(table_name);
+    format!("DELETE FROM {lock_table_name} WHERE id = 1 AND owner = $1 RETURNING id")
+}
+
+/// Lock table exists query. Schema is name of attached database containing
+/// lock table
+#[must_use]
+pub(crate) fn lock_table_exists_query(schema: &str) -> String {
+    format!("SELECT 1 FROM {schema}.sqlite_master WHERE type = 'table' AND name = $1")
+}
+
+/// Get owner which is stored in lock table while lock is held. Owner contains
+/// random token of migrator so migrators of same process do not release lock
+/// of each other
+pub(crate) fn lock_owner<DB>(migrator: &Migrator<DB>) -> String {
+    format!(
+        "{}#{}#{:016x}",
+        migrator.applied_by(),
+        std::process::id(),
+        migrator.lock_token
+    )
+}
+
+/// Interval between attempts of acquiring lock
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Release lock which is not refreshed by its owner within expiry
+async fn release_stale_lock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    expiry: Duration,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (String,): FromRow<'r, <DB as Database>::Row>,
+{
+    let stale_owner = sqlx::query_as::<DB, (String,)>(&release_stale_lock_query(table_name))
+        .bind(format!("-{} seconds", expiry.as_secs()))
+        .fetch_optional(connection)
+        .await?;
+    if let Some((stale_owner,)) = stale_owner {
+        tracing::warn!("released stale migration lock held by {stale_owner}");
+    }
+    Ok(())
+}
+
+/// Release stale lock and try to acquire lock by inserting row in lock table.
+/// Returns whether lock is acquired
+async fn try_acquire_lock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    owner: &str,
+    expiry: Duration,
+) -> Result<bool, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+    for<'r> (String,): FromRow<'r, <DB as Database>::Row>,
+{
+    release_stale_lock::<DB>(&mut *connection, table_name, expiry).await?;
+    let inserted_row = sqlx::query_as::<DB, (i64,)>(&acquire_lock_query(table_name))
+        .bind(owner.to_string())
+        .fetch_optional(connection)
+        .await?;
+    Ok(inserted_row.is_some())
+}
+
+/// Acquire lock by inserting row in lock table. If lock is held by other
+/// migrator, lock is retried till timeout is elapsed or forever if timeout is
+/// not provided. Lock which is not refreshed by its owner within expiry is
+/// considered stale and it is released so lock left by crashed migrator can be
+/// acquired
+pub(crate) async fn acquire_lock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    owner: String,
+    expiry: Duration,
+    timeout: Option<Duration>,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+    for<'r> (String,): FromRow<'r, <DB as Database>::Row>,
+{
+    sqlx::query::<DB>(&create_lock_table_query(table_name))
+        .execute(&mut *connection)
+        .await?;
+    let start = Instant::now();
+    loop {
+        match try_acquire_lock::<DB>(connection, table_name, &owner, expiry).await {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            // database is locked by transaction of other connection which can
+            // be running migration so lock is retried
+            Err(error) if is_timeout_error(&error) => {}
+            Err(error) => return Err(error),
+        }
+        let wait = match timeout {
+            Some(timeout) => {
+                let remaining = timeout.saturating_sub(start.elapsed());
+                if remaining.is_zero() {
+                    return Err(Error::LockTimeout { timeout });
+                }
+                remaining.min(LOCK_RETRY_INTERVAL)
+            }
+            None => LOCK_RETRY_INTERVAL,
+        };
+        crate::timer::sleep(wait)?.await;
+    }
+}
+
+/// Release lock held by owner by deleting row from lock table
+pub(crate) async fn release_lock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    owner: String,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+{
+    sqlx::query::<DB>(&release_lock_query(table_name))
+        .bind(owner)
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+/// Refresh time of lock held by owner so lock is not considered stale
+pub(crate) async fn refresh_lock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    owner: String,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+{
+    let updated_row = sqlx::query_as::<DB, (i64,)>(&refresh_lock_query(table_name))
+        .bind(owner)
+        .fetch_optional(connection)
+        .await?;
+    if updated_row.is_none() {
+        return Err(Error::LockLost);
+    }
+    Ok(())
+}
+
+/// Check whether lock table exists. Lock table is only created while acquiring
+/// lock so inspecting lock do not create it
+async fn lock_table_exists<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+) -> Result<bool, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+{
+    let lock_table_name = lock_table_name(table_name);
+    let (schema, name) = lock_table_name
+        .rsplit_once('.')
+        .unwrap_or(("main", &lock_table_name));
+    let row = sqlx::query_as::<DB, (i64,)>(&lock_table_exists_query(schema))
+        .bind(name.to_string())
+        .fetch_optional(connection)
+        .await?;
+    Ok(row.is_some())
+}
+
+/// Fetch owner of lock along with time when lock was acquired from lock table.
+/// Lock is not held if lock table do not exists
+pub(crate) async fn fetch_lock_holder<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+) -> Result<Option<LockHolder>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+    for<'r> (String, String): FromRow<'r, <DB as Database>::Row>,
+{
+    if !lock_table_exists::<DB>(&mut *connection, table_name).await? {
+        return Ok(None);
+    }
+    let holder = sqlx::query_as::<DB, (String, String)>(&lock_holder_query(table_name))
+        .fetch_optional(connection)
+        .await?;
+    Ok(holder.map(|(owner, locked_at)| LockHolder::new(owner, Some(locked_at))))
+}
+
+/// Drop migration table along with lock table. Tables are not dropped if lock
+/// is held by other migrator while lock table is kept if lock is held by owner
+/// so that lock can still be released. Stale lock is released before checking
+/// lock so lock left by crashed migrator do not prevent dropping tables
+pub(crate) async fn drop_migration_tables<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    owner: &str,
+    expiry: Duration,
+) -> Result<(), Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+    for<'r> (String,): FromRow<'r, <DB as Database>::Row>,
+    for<'r> (String, String): FromRow<'r, <DB as Database>::Row>,
+{
+    // lock is checked and tables are dropped inside same transaction so that
+    // lock cannot be acquired by other migrator in between
+    let mut transaction = connection.begin().await?;
+    let mut drop_lock_table = false;
+    if lock_table_exists::<DB>(&mut *transaction, table_name).await? {
+        release_stale_lock::<DB>(&mut *transaction, table_name, expiry).await?;
+        let holder = sqlx::query_as::<DB, (String, String)>(&lock_holder_query(table_name))
+            .fetch_optional(&mut *transaction)
+            .await?;
+        match holder {
+            Some((holder, _)) if holder != owner => {
+                return Err(Error::LockHeld { session: holder });
+            }
+            Some(_) => {}
+            None => drop_lock_table = true,
+        }
+    }
+    sqlx::query::<DB>(&drop_table_query(table_name))
+        .execute(&mut *transaction)
+        .await?;
+    if drop_lock_table {
+        sqlx::query::<DB>(&drop_table_query(&lock_table_name(table_name)))
+            .execute(&mut *transaction)
+            .await?;
+    }
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Release lock by deleting row from lock table if lock is held by provided
+/// owner
+pub(crate) async fn force_release_lock<DB>(
+    connection: &mut <DB as Database>::Connection,
+    table_name: &str,
+    owner: &str,
+) -> Result<bool, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+{
+    if !lock_table_exists::<DB>(&mut *connection, table_name).await? {
+        return Ok(false);
+    }
+    let deleted_row = sqlx::query_as::<DB, (i64,)>(&force_release_lock_query(table_name))
+        .bind(owner.to_string())
+        .fetch_optional(connection)
+        .await?;
+    Ok(deleted_row.is_some())
+}
+
+/// get settings along with their value which enforce timeout of operation.
+/// Sqlite only waits for lock of database so only busy timeout is set
+pub(crate) fn operation_timeout_settings(timeout: Duration) -> Vec<(String, String)> {
+    vec![(
+        "busy_timeout".to_string(),
+        timeout.as_millis().max(1).to_string(),
+    )]
+}
+
+/// get query which returns current value of pragma. Pragma name is formatted
+/// in query since it cannot be bound
+pub(crate) fn fetch_setting_query(name: &str) -> String {
+    format!("PRAGMA {name}")
+}
+
+/// get query which sets value of pragma. Pragma name and its value is
+/// formatted in query since pragma do not support bind parameter so value is
+/// checked to be integer
+pub(crate) fn update_setting_query(name: &str, value: &str) -> Result<String, Error> {
+    let value = value
+        .parse::<u64>()
+        .map_err(|error| Error::Box(Box::new(error)))?;
+    Ok(format!("PRAGMA {name} = {value}"))
+}
+
+/// Returns true if error is raised because database remained locked till busy
+/// timeout
+pub(crate) fn is_timeout_error(error: &Error) -> bool {
+    let Error::Sqlx(sqlx::Error::Database(database_error)) = error else {
+        return false;
+    };
+    // primary result code is stored in lower byte of extended result code and
+    // 5 is SQLITE_BUSY
+    database_error
+        .try_downcast_ref::<SqliteError>()
+        .and_then(|sqlite_error| sqlite_error.code()?.parse::<i32>().ok())
+        .is_some_and(|code| code & 0xff == 5)
+}
+
+/// Returns class of transient error if error is raised due to concurrent
+/// transaction. Sqlite do not detect deadlock or serialization failure instead
+/// database remains locked till busy timeout
+pub(crate) fn transient_error(error: &Error) -> Option<TransientError> {
+    is_timeout_error(error).then_some(TransientError::LockTimeout)
+}
+
+/// Update pragmas of connection and return name and previous value of pragmas
+pub(crate) async fn replace_settings<DB>(
+    connection: &mut <DB as Database>::Connection,
+    settings: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'r> (i64,): FromRow<'r, <DB as Database>::Row>,
+{
+    let mut previous_settings = Vec::with_capacity(settings.len());
+    for (name, value) in settings {
+        let (previous_value,) = sqlx::query_as::<DB, (i64,)>(&fetch_setting_query(&name))
+            .fetch_one(&mut *connection)
+            .await?;
+        sqlx::query::<DB>(&update_setting_query(&name, &value)?)
+            .execute(&mut *connection)
+            .await?;
+        previous_settings.push((name, previous_value.to_string()));
+    }
+    Ok(previous_settings)
+}
+
 /// Drop table
 #[must_use]
 pub(crate) fn drop_table_query(table_name: &str) -> String {
@@ -26,13 +478,52 @@ pub(crate) fn drop_table_query(table_name: &str) -> String {
 
 /// fetch rows
 pub(crate) fn fetch_rows_query(table_name: &str) -> String {
-    format!("SELECT id, app, name, applied_time FROM {table_name}")
+    format!(
+        "SELECT id, app, name, applied_time, checksum, duration_ms, applied_by, \
+         applied_operations FROM {table_name}"
+    )
 }
 
 /// add migration query
 #[must_use]
 pub(crate) fn add_migration_query(table_name: &str) -> String {
-    format!("INSERT INTO {table_name}(app, name) VALUES ($1, $2)")
+    format!("INSERT INTO {table_name}(app, name, checksum, applied_by) VALUES ($1, $2, $3, $4)")
+}
+
+/// Add migration with applied time query
+#[must_use]
+pub(crate) fn add_migration_with_applied_time_query(table_name: &str) -> String {
+    format!(
+        "INSERT INTO {table_name}(app, name, checksum, applied_by, applied_time) VALUES ($1, $2, \
+         $3, $4, $5)"
+    )
+}
+
+/// Add migration with duration query
+#[must_use]
+pub(crate) fn add_migration_with_duration_query(table_name: &str) -> String {
+    format!(
+        "INSERT INTO {table_name}(app, name, checksum, applied_by, duration_ms) VALUES ($1, $2, \
+         $3, $4, $5)"
+    )
+}
+
+/// Update migration duration query
+#[must_use]
+pub(crate) fn update_duration_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET duration_ms = $1 WHERE app = $2 AND name = $3")
+}
+
+/// Update number of applied operations of partially applied migration query
+#[must_use]
+pub(crate) fn update_applied_operations_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET applied_operations = $1 WHERE app = $2 AND name = $3")
+}
+
+/// Update checksum of applied migration query
+#[must_use]
+pub(crate) fn update_checksum_query(table_name: &str) -> String {
+    format!("UPDATE {table_name} SET checksum = $1 WHERE app = $2 AND name = $3")
 }
 
 /// delete migration query
@@ -41,13 +532,34 @@ pub(crate) fn delete_migration_query(table_name: &str) -> String {
     format!("DELETE FROM {table_name} WHERE app = $1 AND name = $2")
 }
 
+/// Fetch history rows query
+pub(crate) fn fetch_history_rows_query(table_name: &str) -> String {
+    format!("SELECT id, app, name, event, event_time, performed_by FROM {table_name} ORDER BY id")
+}
+
+/// Add history query
+#[must_use]
+pub(crate) fn add_history_query(table_name: &str) -> String {
+    format!("INSERT INTO {table_name}(app, name, event, performed_by) VALUES ($1, $2, $3, $4)")
+}
+
 #[async_trait::async_trait]
 impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
     async fn ensure_migration_table_exists(
         &self,
         connection: &mut <Sqlite as Database>::Connection,
     ) -> Result<(), Error> {
-        sqlx::query(&create_migrator_table_query(&self.table_name()))
+        let table_name = self.table_name();
+        sqlx::query(&create_migrator_table_query(&table_name))
+            .execute(&mut *connection)
+            .await?;
+        add_missing_columns::<Sqlite>(
+            &mut *connection,
+            &table_name,
+            migrator_table_upgrade_columns(),
+        )
+        .await?;
+        sqlx::query(&create_history_table_query(&self.history_table_name()))
             .execute(connection)
             .await?;
         Ok(())
@@ -57,18 +569,55 @@ impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
         &self,
         connection: &mut <Sqlite as Database>::Connection,
     ) -> Result<(), Error> {
-        sqlx::query(&drop_table_query(&self.table_name()))
+        drop_migration_tables::<Sqlite>(
+            connection,
+            &self.table_name(),
+            &lock_owner(self),
+            self.lock_expiry(),
+        )
+        .await
+    }
+
+    async fn add_migration_to_db_table(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        migration: &Box<dyn Migration<Sqlite>>,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
             .execute(connection)
             .await?;
         Ok(())
     }
 
-    async fn add_migration_to_db_table(
+    async fn add_migration_to_db_table_with_duration(
         &self,
         connection: &mut <Sqlite as Database>::Connection,
         migration: &Box<dyn Migration<Sqlite>>,
+        duration: Duration,
     ) -> Result<(), Error> {
-        sqlx::query(&add_migration_query(&self.table_name()))
+        sqlx::query(&add_migration_with_duration_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_duration(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        migration: &Box<dyn Migration<Sqlite>>,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_duration_query(&self.table_name()))
+            .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
             .bind(migration.app())
             .bind(migration.name())
             .execute(connection)
@@ -76,6 +625,52 @@ impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
         Ok(())
     }
 
+    async fn update_applied_operations(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        migration: &Box<dyn Migration<Sqlite>>,
+        applied_operations: Option<usize>,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_applied_operations_query(&self.table_name()))
+            .bind(applied_operations.map(|count| i64::try_from(count).unwrap_or(i64::MAX)))
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_migration_checksum(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        migration: &Box<dyn Migration<Sqlite>>,
+    ) -> Result<(), Error> {
+        sqlx::query(&update_checksum_query(&self.table_name()))
+            .bind(migration.checksum())
+            .bind(migration.app())
+            .bind(migration.name())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_migration_to_db_table_with_applied_time(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        migration: &Box<dyn Migration<Sqlite>>,
+        applied_time: &str,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_migration_with_applied_time_query(&self.table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(migration.checksum())
+            .bind(self.applied_by())
+            .bind(applied_time)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
     async fn delete_migration_from_db_table(
         &self,
         connection: &mut <Sqlite as Database>::Connection,
@@ -100,14 +695,120 @@ impl DatabaseOperation<Sqlite> for Migrator<Sqlite> {
         )
     }
 
-    async fn lock(&self, _connection: &mut <Sqlite as Database>::Connection) -> Result<(), Error> {
+    async fn add_history_to_db_table(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        migration: &Box<dyn Migration<Sqlite>>,
+        event: HistoryEvent,
+    ) -> Result<(), Error> {
+        sqlx::query(&add_history_query(&self.history_table_name()))
+            .bind(migration.app())
+            .bind(migration.name())
+            .bind(event.as_str())
+            .bind(self.applied_by())
+            .execute(connection)
+            .await?;
         Ok(())
     }
 
-    async fn unlock(
+    async fn fetch_history_from_db(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+    ) -> Result<Vec<MigrationHistorySqlRow>, Error> {
+        Ok(
+            sqlx::query_as::<_, MigrationHistorySqlRow>(&fetch_history_rows_query(
+                &self.history_table_name(),
+            ))
+            .fetch_all(connection)
+            .await?,
+        )
+    }
+
+    async fn is_transactional_ddl_supported(
         &self,
         _connection: &mut <Sqlite as Database>::Connection,
+    ) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn is_operation_progress_supported(&self) -> bool {
+        true
+    }
+
+    async fn set_operation_timeout(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<Vec<(String, String)>, Error> {
+        replace_settings::<Sqlite>(connection, operation_timeout_settings(timeout)).await
+    }
+
+    async fn restore_operation_timeout(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        settings: Vec<(String, String)>,
     ) -> Result<(), Error> {
+        replace_settings::<Sqlite>(connection, settings).await?;
         Ok(())
     }
+
+    fn is_timeout_error(&self, error: &Error) -> bool {
+        is_timeout_error(error)
+    }
+
+    fn transient_error(&self, error: &Error) -> Option<TransientError> {
+        transient_error(error)
+    }
+
+    async fn lock(&self, connection: &mut <Sqlite as Database>::Connection) -> Result<(), Error> {
+        acquire_lock::<Sqlite>(
+            connection,
+            &self.table_name(),
+            lock_owner(self),
+            self.lock_expiry(),
+            None,
+        )
+        .await
+    }
+
+    async fn lock_with_timeout(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        acquire_lock::<Sqlite>(
+            connection,
+            &self.table_name(),
+            lock_owner(self),
+            self.lock_expiry(),
+            Some(timeout),
+        )
+        .await
+    }
+
+    async fn refresh_lock(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+    ) -> Result<(), Error> {
+        refresh_lock::<Sqlite>(connection, &self.table_name(), lock_owner(self)).await
+    }
+
+    async fn unlock(&self, connection: &mut <Sqlite as Database>::Connection) -> Result<(), Error> {
+        release_lock::<Sqlite>(connection, &self.table_name(), lock_owner(self)).await
+    }
+
+    async fn fetch_lock_holder(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+    ) -> Result<Option<LockHolder>, Error> {
+        fetch_lock_holder::<Sqlite>(connection, &self.table_name()).await
+    }
+
+    async fn force_unlock(
+        &self,
+        connection: &mut <Sqlite as Database>::Connection,
+        session: &str,
+    ) -> Result<bool, Error> {
+        force_release_lock::<Sqlite>(connection, &self.table_name(), session).await
+    }
 }
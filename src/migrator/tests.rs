@@ -1,9 +1,19 @@
-use sqlx::{Database, Sqlite, SqlitePool};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
+use sqlx::{Connection as _, Database, Sqlite, SqlitePool};
+
+use super::recorder::StatementRecorder;
 use super::{DatabaseOperation, Info, Migrate, Migrator};
 use crate::error::Error;
-use crate::migration::{AppliedMigrationSqlRow, Migration};
-use crate::migrator::Plan;
+use crate::export::Export as _;
+use crate::migration::{
+    AppliedMigrationSqlRow, Direction, HistoryEvent, Migration, MigrationHistorySqlRow,
+};
+use crate::migrator::{MigrationListener, Plan, RetryPolicy, RunReport, TransientError};
+use crate::operation::Operation;
+use crate::sync::Synchronize as _;
 use crate::vec_box;
 
 #[test]
@@ -43,6 +53,8 @@ struct CustomMigrator {
     internal_migrator: Migrator<Sqlite>,
     migrations: Vec<Box<dyn Migration<Sqlite>>>,
     applied_migrations: Vec<AppliedMigrationSqlRow>,
+    lock_held_by_other: bool,
+    retry_policy: RetryPolicy,
 }
 
 impl CustomMigrator {
@@ -123,15 +135,34 @@ impl DatabaseOperation<Sqlite> for CustomMigrator {
         Ok(())
     }
 
+    async fn lock_with_timeout(
+        &self,
+        _connection: &mut <Sqlite as Database>::Connection,
+        timeout: std::time::Duration,
+    ) -> Result<(), Error> {
+        if self.lock_held_by_other {
+            return Err(Error::LockTimeout { timeout });
+        }
+        Ok(())
+    }
+
     async fn unlock(
         &self,
         _connection: &mut <Sqlite as Database>::Connection,
     ) -> Result<(), Error> {
         Ok(())
     }
+
+    fn transient_error(&self, error: &Error) -> Option<TransientError> {
+        self.internal_migrator.transient_error(error)
+    }
 }
 
-impl Migrate<Sqlite> for CustomMigrator {}
+impl Migrate<Sqlite> for CustomMigrator {
+    fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy.clone()
+    }
+}
 
 macro_rules! migration {
     ($op:ty, $name:literal, $parents:expr, $replaces:expr, $run_before:expr) => {
@@ -840,3 +871,2258 @@ async fn revert_virtual_plan_size() {
     assert!(plan_till_b_iter.next() == Some(&&(Box::new(B) as Box<dyn Migration<Sqlite>>)));
     assert!(plan_till_b_iter.next().is_none());
 }
+
+#[tokio::test]
+async fn checksum_mismatch() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE a (id INTEGER)", "DROP TABLE a"))
+        }
+    }
+    struct ModifiedA;
+    impl Migration<Sqlite> for ModifiedA {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE a (id INTEGER, name TEXT)", "DROP TABLE a"))
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    let applied_migrations = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        applied_migrations[0].checksum(),
+        (Box::new(A) as Box<dyn Migration<Sqlite>>)
+            .checksum()
+            .as_deref()
+    );
+    let mut modified_migrator = Migrator::<Sqlite>::default();
+    modified_migrator
+        .add_migrations(vec_box!(ModifiedA))
+        .unwrap();
+    let plan = modified_migrator
+        .generate_migration_plan(&mut conn, Some(&Plan::apply_all()))
+        .await;
+    let stored_checksum = (Box::new(A) as Box<dyn Migration<Sqlite>>)
+        .checksum()
+        .unwrap();
+    let current_checksum = (Box::new(ModifiedA) as Box<dyn Migration<Sqlite>>)
+        .checksum()
+        .unwrap();
+    assert_eq!(
+        plan.err().map(|e| e.to_string()),
+        Some(format!(
+            "checksum mismatch found for applied migration test - a: stored {stored_checksum}, \
+             current {current_checksum}"
+        ))
+    );
+
+    // checksum is only compared while applying migrations
+    for plan in [Plan::apply_all().fake(true), Plan::revert_all()] {
+        assert!(
+            modified_migrator
+                .generate_migration_plan(&mut conn, Some(&plan))
+                .await
+                .is_ok()
+        );
+    }
+
+    // accepting changed checksum stores current checksum
+    modified_migrator
+        .run(&mut conn, &Plan::apply_all().accept_checksum(true))
+        .await
+        .unwrap();
+    let applied_migrations = modified_migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        applied_migrations[0].checksum(),
+        Some(current_checksum.as_str())
+    );
+    modified_migrator
+        .run(&mut conn, &Plan::apply_all())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn old_migrator_table_upgraded() {
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    sqlx::query(
+        "CREATE TABLE _sqlx_migrator_migrations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        app TEXT NOT NULL,
+        name TEXT NOT NULL,
+        applied_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE (app, name)
+    )",
+    )
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO _sqlx_migrator_migrations(app, name) VALUES ('test', 'a')")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    let migrator = Migrator::<Sqlite>::default();
+    migrator
+        .ensure_migration_table_exists(&mut conn)
+        .await
+        .unwrap();
+    let applied_migrations = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(applied_migrations.len(), 1);
+    assert!(applied_migrations[0].checksum().is_none());
+}
+
+#[tokio::test]
+async fn applied_duration_and_applied_by() {
+    struct A;
+    migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "b", vec_box!(A), vec_box!(), vec_box!());
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default().set_applied_by("tester");
+    migrator.add_migrations(vec_box!(A, B)).unwrap();
+    migrator
+        .run(&mut conn, &Plan::apply_count(1))
+        .await
+        .unwrap();
+    migrator
+        .run(&mut conn, &Plan::apply_all().fake(true))
+        .await
+        .unwrap();
+    let applied_migrations = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(applied_migrations.len(), 2);
+    assert!(applied_migrations[0].duration().is_some());
+    assert_eq!(applied_migrations[0].applied_by(), Some("tester"));
+    assert!(applied_migrations[1].duration().is_none());
+    assert_eq!(applied_migrations[1].applied_by(), Some("tester"));
+}
+
+#[tokio::test]
+async fn history_recorded() {
+    struct A;
+    migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    migrator.run(&mut conn, &Plan::revert_all()).await.unwrap();
+    migrator
+        .run(&mut conn, &Plan::apply_all().fake(true))
+        .await
+        .unwrap();
+    migrator
+        .run(&mut conn, &Plan::revert_all().fake(true))
+        .await
+        .unwrap();
+    let history = migrator.fetch_history_from_db(&mut conn).await.unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(MigrationHistorySqlRow::event)
+            .collect::<Vec<_>>(),
+        vec![
+            HistoryEvent::Apply.as_str(),
+            HistoryEvent::Revert.as_str(),
+            HistoryEvent::FakeApply.as_str(),
+            HistoryEvent::FakeRevert.as_str()
+        ]
+    );
+    assert!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // history is preserved when migration table is dropped
+    migrator
+        .drop_migration_table_if_exists(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        migrator
+            .fetch_history_from_db(&mut conn)
+            .await
+            .unwrap()
+            .len(),
+        4
+    );
+}
+
+#[tokio::test]
+async fn dry_run_rolled_back() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(
+                ("CREATE TABLE a (id INTEGER)", "DROP TABLE a"),
+                ("CREATE INDEX a_id ON a (id)", "DROP INDEX a_id")
+            )
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    let dry_run_migrations = migrator
+        .dry_run(&mut conn, &Plan::apply_all())
+        .await
+        .unwrap();
+    assert_eq!(dry_run_migrations.len(), 1);
+    assert!(dry_run_migrations[0].executed());
+    assert_eq!(
+        dry_run_migrations[0].statements(),
+        [
+            Some("CREATE TABLE a (id INTEGER)".to_string()),
+            Some("CREATE INDEX a_id ON a (id)".to_string())
+        ]
+    );
+    let (table_count,) =
+        sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM sqlite_master WHERE name = 'a'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+    assert_eq!(table_count, 0);
+    assert!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    let dry_run_migrations = migrator
+        .dry_run(&mut conn, &Plan::revert_all())
+        .await
+        .unwrap();
+    assert_eq!(
+        dry_run_migrations[0].statements(),
+        [
+            Some("DROP INDEX a_id".to_string()),
+            Some("DROP TABLE a".to_string())
+        ]
+    );
+    assert_eq!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn dry_run_statements_not_captured() {
+    // operation which executes statement without exposing it
+    struct HiddenOperation;
+    #[async_trait::async_trait]
+    impl Operation<Sqlite> for HiddenOperation {
+        async fn up(&self, connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+            sqlx::query("CREATE TABLE hidden (id INTEGER)")
+                .execute(connection)
+                .await?;
+            Ok(())
+        }
+    }
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(
+                ("CREATE TABLE a (id INTEGER)", "DROP TABLE a"),
+                HiddenOperation
+            )
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    // sqlite statements are not recorded so hidden statement cannot be captured
+    let error = migrator
+        .dry_run(&mut conn, &Plan::apply_all())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Error::DryRunStatementsNotCaptured {
+            operation_index: 1,
+            ..
+        }
+    ));
+    assert!(
+        migrator
+            .fetch_lock_holder(&mut conn)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let (table_count,) = sqlx::query_as::<_, (i64,)>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('a', 'hidden')",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+    assert_eq!(table_count, 0);
+}
+
+#[tokio::test]
+async fn statement_recorder_records_query_events() {
+    let recorder = StatementRecorder::new();
+    recorder
+        .record(async {
+            tracing::debug!(target: "sqlx::query", summary = "SELECT 1", db.statement = "");
+            tracing::debug!(
+                target: "sqlx::query",
+                summary = "CREATE TABLE a …",
+                db.statement = "\n\nCREATE TABLE a (id INTEGER)\n"
+            );
+            tracing::debug!(target: "other", summary = "SELECT 2");
+        })
+        .await;
+    tracing::debug!(target: "sqlx::query", summary = "SELECT 3", db.statement = "");
+    assert_eq!(recorder.take(), ["SELECT 1", "CREATE TABLE a (id INTEGER)"]);
+    assert!(recorder.take().is_empty());
+}
+#[tokio::test]
+async fn sql_migrations_loaded_from_directory() {
+    let directory = std::env::temp_dir().join(format!(
+        "sqlx_migrator_sql_migrations_{}",
+        std::process::id()
+    ));
+    let files = [
+        (
+            "main/0001_create_user/up.sql",
+            "CREATE TABLE user (id INTEGER);\nCREATE TABLE account (id INTEGER);",
+        ),
+        (
+            "main/0001_create_user/down.sql",
+            "DROP TABLE account;\nDROP TABLE user;",
+        ),
+        (
+            "main/0002_create_index/up.sql",
+            "-- migrator:no-transaction\nCREATE INDEX user_id ON user (id);",
+        ),
+        ("main/0002_create_index/down.sql", "DROP INDEX user_id;"),
+        (
+            "other/0001_create_post/up.sql",
+            "-- migrator:parent main/0001_create_user\n-- migrator:destructible\nCREATE TABLE \
+             post (id INTEGER);",
+        ),
+    ];
+    for (path, content) in files {
+        let path = directory.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let migrations = crate::sql::load_migrations::<Sqlite>(&directory);
+    std::fs::remove_dir_all(&directory).unwrap();
+    let migrations = migrations.unwrap();
+    assert_eq!(
+        migrations
+            .iter()
+            .map(|migration| (migration.app(), migration.name(), migration.is_atomic()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("main", "0001_create_user", true),
+            ("main", "0002_create_index", false),
+            ("other", "0001_create_post", true)
+        ]
+    );
+    assert!(migrations[2].operations()[0].is_destructible());
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(migrations).unwrap();
+    let plan = migrator
+        .generate_migration_plan(&mut conn, Some(&Plan::apply_name("other", &None)))
+        .await
+        .unwrap();
+    assert_eq!(
+        plan.iter()
+            .map(|migration| migration.name())
+            .collect::<Vec<_>>(),
+        vec!["0001_create_user", "0001_create_post"]
+    );
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    let (table_count,) = sqlx::query_as::<_, (i64,)>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('user', 'account', 'post', 'user_id')",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+    assert_eq!(table_count, 4);
+    let result = migrator.run(&mut conn, &Plan::revert_all()).await;
+    assert!(matches!(
+        result,
+        Err(Error::OperationFailed { source, .. })
+            if matches!(*source, Error::IrreversibleOperation)
+    ));
+}
+
+#[test]
+fn sql_migration_invalid_directive() {
+    let result = crate::sql::SqlMigration::new(
+        "main",
+        "0001_invalid",
+        "-- migrator:parent invalid\nSELECT 1;",
+        None,
+    );
+    assert!(matches!(result, Err(Error::InvalidSqlMigration { .. })));
+    let result = crate::sql::SqlMigration::new(
+        "main",
+        "0001_invalid",
+        "-- migrator:unknown\nSELECT 1;",
+        None,
+    );
+    assert!(matches!(result, Err(Error::InvalidSqlMigration { .. })));
+}
+
+#[cfg(feature = "macros")]
+#[tokio::test]
+async fn sql_migrations_embedded() {
+    /// App, name, parents, atomic and destructible of sql migration
+    type SqlMigrationMetadata = (String, String, Vec<(String, String)>, bool, bool);
+    let metadata = |migrations: &[Box<dyn Migration<Sqlite>>]| {
+        migrations
+            .iter()
+            .map(|migration| {
+                (
+                    migration.app().to_string(),
+                    migration.name().to_string(),
+                    migration
+                        .parents()
+                        .iter()
+                        .map(|parent| (parent.app().to_string(), parent.name().to_string()))
+                        .collect(),
+                    migration.is_atomic(),
+                    migration.operations()[0].is_destructible(),
+                )
+            })
+            .collect::<Vec<SqlMigrationMetadata>>()
+    };
+    let migrations: Vec<Box<dyn Migration<Sqlite>>> =
+        crate::embed_migrations!("src/migrator/fixtures/sql_migrations");
+    // directives parsed at compile time are same as directives parsed while
+    // loading migrations at runtime
+    let loaded_migrations =
+        crate::sql::load_migrations::<Sqlite>("src/migrator/fixtures/sql_migrations").unwrap();
+    assert_eq!(metadata(&migrations), metadata(&loaded_migrations));
+    let parent = |app: &str, name: &str| (app.to_string(), name.to_string());
+    assert_eq!(
+        metadata(&migrations),
+        vec![
+            (
+                "main".to_string(),
+                "0001_create_user".to_string(),
+                vec![],
+                true,
+                false
+            ),
+            (
+                "main".to_string(),
+                "0002_create_index".to_string(),
+                vec![parent("main", "0001_create_user")],
+                false,
+                false
+            ),
+            (
+                "other".to_string(),
+                "2_create_post".to_string(),
+                vec![parent("main", "0001_create_user")],
+                true,
+                true
+            ),
+            (
+                "other".to_string(),
+                "10_create_comment".to_string(),
+                vec![parent("other", "2_create_post")],
+                true,
+                false
+            ),
+        ]
+    );
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(migrations).unwrap();
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    migrator.run(&mut conn, &Plan::revert_all()).await.unwrap();
+    assert!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[cfg(feature = "macros")]
+#[tokio::test]
+async fn derive_migration() {
+    #[derive(crate::migration::Migration)]
+    #[migration(db = Sqlite, app = "test")]
+    struct A;
+    #[derive(crate::migration::Migration)]
+    #[migration(
+        db = Sqlite,
+        app = "test",
+        name = "b",
+        parents(A),
+        operations(("CREATE TABLE b (id INTEGER)", "DROP TABLE b")),
+        replaces(("test", "old_b")),
+        run_before(("test", "c")),
+        atomic = false
+    )]
+    struct B;
+    #[derive(crate::migration::Migration)]
+    #[migration(db = Sqlite, app = "test", name = "c", virtual = true)]
+    struct C;
+    #[derive(crate::migration::Migration)]
+    #[migration(
+        db = Sqlite,
+        app = "test",
+        name = "d",
+        parents(A),
+        operations(("CREATE TABLE d (id INTEGER)", "DROP TABLE d"))
+    )]
+    struct D;
+    assert_eq!(Migration::<Sqlite>::name(&A), "tests");
+    assert_eq!(Migration::<Sqlite>::parents(&B).len(), 1);
+    assert_eq!(Migration::<Sqlite>::operations(&B).len(), 1);
+    assert_eq!(Migration::<Sqlite>::replaces(&B)[0].name(), "old_b");
+    assert_eq!(Migration::<Sqlite>::run_before(&B)[0].name(), "c");
+    assert!(!Migration::<Sqlite>::is_atomic(&B));
+    assert!(Migration::<Sqlite>::is_virtual(&C));
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(D)).unwrap();
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    assert_eq!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn sync_sqlx_native_migrations() {
+    struct A;
+    migration!(A, "1_a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "2_b", vec_box!(A), vec_box!(), vec_box!());
+    struct C;
+    migration!(C, "3_c", vec_box!(B), vec_box!(), vec_box!());
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    sqlx::raw_sql(
+        "CREATE TABLE _sqlx_migrations (version BIGINT PRIMARY KEY, description TEXT NOT NULL, \
+         installed_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, success BOOLEAN NOT NULL, \
+         checksum BLOB NOT NULL, execution_time BIGINT NOT NULL);
+         INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+         VALUES (1, 'a', TRUE, x'00', 0), (2, 'b', TRUE, x'00', 0), (3, 'c', FALSE, x'00', 0);",
+    )
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(C)).unwrap();
+    migrator
+        .ensure_migration_table_exists(&mut conn)
+        .await
+        .unwrap();
+    let old_migrator = crate::sync::SqlxNativeMigrator::new(|version, description| {
+        Some(("test".to_string(), format!("{version}_{description}")))
+    });
+    migrator.sync(&mut conn, &old_migrator).await.unwrap();
+    let applied_migrations = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        applied_migrations
+            .iter()
+            .map(AppliedMigrationSqlRow::name)
+            .collect::<Vec<_>>(),
+        vec!["1_a", "2_b"]
+    );
+}
+
+#[tokio::test]
+async fn sync_other_old_migrators() {
+    struct A;
+    migration!(A, "1_a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "2_b", vec_box!(A), vec_box!(), vec_box!());
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    sqlx::raw_sql(
+        "CREATE TABLE refinery_schema_history (version INT4 PRIMARY KEY, name VARCHAR(255), \
+         applied_on VARCHAR(255), checksum VARCHAR(255));
+         INSERT INTO refinery_schema_history (version, name, applied_on) VALUES (1, 'a', \
+         '2024-01-01T23:30:00.123456789-01:00');
+         CREATE TABLE __diesel_schema_migrations (version VARCHAR(50) PRIMARY KEY NOT NULL, run_on \
+         TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP);
+         INSERT INTO __diesel_schema_migrations (version) VALUES ('1_a'), ('2_b');
+         CREATE TABLE schema_migrations (version BIGINT NOT NULL PRIMARY KEY, dirty BOOLEAN NOT \
+         NULL);
+         INSERT INTO schema_migrations (version, dirty) VALUES (1, TRUE);",
+    )
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(B)).unwrap();
+    migrator
+        .ensure_migration_table_exists(&mut conn)
+        .await
+        .unwrap();
+    let applied_names = |applied_migrations: Vec<AppliedMigrationSqlRow>| {
+        applied_migrations
+            .iter()
+            .map(|migration| migration.name().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let refinery_migrator = crate::sync::RefineryMigrator::new(|version, name| {
+        Some(("test".to_string(), format!("{version}_{name}")))
+    });
+    // migration table is created by dry run so it can be run before any sync
+    let fresh_migrator = Migrator::<Sqlite>::default()
+        .set_table_prefix("fresh")
+        .unwrap();
+    let report = fresh_migrator
+        .sync_dry_run(&mut conn, &refinery_migrator)
+        .await
+        .unwrap();
+    assert_eq!(report.unknown().len(), 1);
+    migrator.sync(&mut conn, &refinery_migrator).await.unwrap();
+    let applied_migrations = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    // applied time stored by refinery in RFC 3339 format is converted to UTC
+    assert_eq!(applied_migrations[0].applied_time(), "2024-01-02 00:30:00");
+    assert_eq!(applied_names(applied_migrations), vec!["1_a"]);
+
+    let golang_migrator = crate::sync::GolangMigrateMigrator::new([1, 2], |version| {
+        Some(("test".to_string(), format!("{version}")))
+    });
+    assert!(matches!(
+        migrator.sync(&mut conn, &golang_migrator).await,
+        Err(Error::DirtyOldMigration { .. })
+    ));
+
+    let diesel_migrator =
+        crate::sync::DieselMigrator::new(|version| Some(("test".to_string(), version.to_string())));
+    migrator.sync(&mut conn, &diesel_migrator).await.unwrap();
+    assert_eq!(
+        applied_names(
+            migrator
+                .fetch_applied_migration_from_db(&mut conn)
+                .await
+                .unwrap()
+        ),
+        vec!["1_a", "2_b"]
+    );
+}
+
+#[tokio::test]
+async fn sync_report_and_applied_time() {
+    struct A;
+    migration!(A, "1_a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "2_b", vec_box!(A), vec_box!(), vec_box!());
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    sqlx::raw_sql(
+        "CREATE TABLE __diesel_schema_migrations (version VARCHAR(50) PRIMARY KEY NOT NULL, \
+         run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP);
+         INSERT INTO __diesel_schema_migrations (version, run_on) VALUES ('1_a', '2020-01-01 \
+         10:00:00'), ('2_b', '2020-01-02 10:00:00'), ('3_c', '2020-01-03 10:00:00');",
+    )
+    .execute(&mut *conn)
+    .await
+    .unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(B)).unwrap();
+    migrator
+        .ensure_migration_table_exists(&mut conn)
+        .await
+        .unwrap();
+    migrator
+        .add_migration_to_db_table(&mut conn, &(Box::new(A) as Box<dyn Migration<Sqlite>>))
+        .await
+        .unwrap();
+    let diesel_migrator =
+        crate::sync::DieselMigrator::new(|version| Some(("test".to_string(), version.to_string())));
+    let app_names = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| ("test".to_string(), (*name).to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let dry_run_report = migrator
+        .sync_dry_run(&mut conn, &diesel_migrator)
+        .await
+        .unwrap();
+    assert_eq!(dry_run_report.inserted(), app_names(&["2_b"]));
+    assert_eq!(dry_run_report.already_applied(), app_names(&["1_a"]));
+    assert_eq!(dry_run_report.unknown(), app_names(&["3_c"]));
+    assert_eq!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let report = migrator.sync(&mut conn, &diesel_migrator).await.unwrap();
+    assert_eq!(report, dry_run_report);
+    let applied_migrations = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(applied_migrations.len(), 2);
+    assert_eq!(applied_migrations[1].applied_time(), "2020-01-02 10:00:00");
+}
+
+#[tokio::test]
+#[expect(clippy::too_many_lines)]
+async fn export_applied_migrations() {
+    struct A;
+    migration!(A, "1_a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "2_b", vec_box!(A), vec_box!(), vec_box!());
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(B)).unwrap();
+    migrator
+        .run(&mut conn, &Plan::apply_count(1))
+        .await
+        .unwrap();
+    migrator
+        .add_migration_to_db_table(
+            &mut conn,
+            &(Box::new(("test", "3_unknown")) as Box<dyn Migration<Sqlite>>),
+        )
+        .await
+        .unwrap();
+    let app_names = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| ("test".to_string(), (*name).to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let sqlx_native_target = crate::export::SqlxNativeTarget::new(|_app, name| {
+        let (version, description) = name.split_once('_')?;
+        if description == "unknown" {
+            return None;
+        }
+        Some((version.parse().ok()?, description.to_string(), vec![]))
+    });
+    let report = migrator
+        .export(&mut conn, &sqlx_native_target)
+        .await
+        .unwrap();
+    assert_eq!(report.exported(), app_names(&["1_a"]));
+    assert_eq!(report.skipped(), app_names(&["3_unknown"]));
+    let report = migrator
+        .export(&mut conn, &sqlx_native_target)
+        .await
+        .unwrap();
+    assert_eq!(report.already_exported(), app_names(&["1_a"]));
+
+    // exported migrations can be synced back using sqlx native old migrator
+    let old_migrator = crate::sync::SqlxNativeMigrator::new(|version, description| {
+        Some(("test".to_string(), format!("{version}_{description}")))
+    });
+    let mut synced_migrator = Migrator::<Sqlite>::default()
+        .set_table_prefix("synced")
+        .unwrap();
+    synced_migrator.add_migrations(vec_box!(B)).unwrap();
+    synced_migrator
+        .ensure_migration_table_exists(&mut conn)
+        .await
+        .unwrap();
+    let sync_report = synced_migrator
+        .sync(&mut conn, &old_migrator)
+        .await
+        .unwrap();
+    assert_eq!(sync_report.inserted(), app_names(&["1_a"]));
+
+    let target_migrator = Migrator::<Sqlite>::default()
+        .set_table_prefix("target")
+        .unwrap();
+    let report = migrator.export(&mut conn, &target_migrator).await.unwrap();
+    assert_eq!(report.exported(), app_names(&["1_a", "3_unknown"]));
+    // target migrator is locked during export and released afterwards
+    let (count,): (i64,) = sqlx::query_as(&format!(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name = '{}_lock'",
+        target_migrator.table_name()
+    ))
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+    assert_eq!(count, 1);
+    assert!(
+        target_migrator
+            .fetch_lock_holder(&mut conn)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let source_applied = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    let target_applied = target_migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        source_applied
+            .iter()
+            .map(|row| (row.name(), row.applied_time(), row.checksum()))
+            .collect::<Vec<_>>(),
+        target_applied
+            .iter()
+            .map(|row| (row.name(), row.applied_time(), row.checksum()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        target_migrator
+            .fetch_history_from_db(&mut conn)
+            .await
+            .unwrap()
+            .iter()
+            .map(MigrationHistorySqlRow::event)
+            .collect::<Vec<_>>(),
+        [HistoryEvent::Export.as_str(); 2]
+    );
+}
+
+#[tokio::test]
+async fn export_status_mismatch() {
+    struct A;
+    migration!(A, "1_a", vec_box!(), vec_box!(), vec_box!());
+    struct B;
+    migration!(B, "2_b", vec_box!(A), vec_box!(), vec_box!());
+    // target which returns status only for first migration
+    struct PartialTarget {
+        events: Mutex<Vec<&'static str>>,
+    }
+    #[async_trait::async_trait]
+    impl crate::export::TargetMigrator<Sqlite> for PartialTarget {
+        async fn ensure_target_table_exists(
+            &self,
+            connection: &mut sqlx::SqliteConnection,
+        ) -> Result<(), Error> {
+            sqlx::query("CREATE TABLE IF NOT EXISTS partial_target (name TEXT)")
+                .execute(connection)
+                .await?;
+            Ok(())
+        }
+
+        async fn add_applied_migrations(
+            &self,
+            connection: &mut sqlx::SqliteConnection,
+            migrations: &[(&super::BoxMigration<Sqlite>, &AppliedMigrationSqlRow)],
+        ) -> Result<Vec<crate::export::ExportStatus>, Error> {
+            self.events.lock().unwrap().push("add");
+            for (migration, _) in migrations {
+                sqlx::query("INSERT INTO partial_target (name) VALUES ($1)")
+                    .bind(migration.name())
+                    .execute(&mut *connection)
+                    .await?;
+            }
+            Ok(vec![crate::export::ExportStatus::Exported])
+        }
+
+        async fn lock_target(&self, _connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+            self.events.lock().unwrap().push("lock");
+            Ok(())
+        }
+
+        async fn unlock_target(
+            &self,
+            _connection: &mut sqlx::SqliteConnection,
+        ) -> Result<(), Error> {
+            self.events.lock().unwrap().push("unlock");
+            Ok(())
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(B)).unwrap();
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+
+    let target = PartialTarget {
+        events: Mutex::new(vec![]),
+    };
+    let error = migrator.export(&mut conn, &target).await.unwrap_err();
+    assert!(matches!(
+        error,
+        Error::ExportStatusMismatch {
+            migrations: 2,
+            statuses: 1
+        }
+    ));
+    assert_eq!(*target.events.lock().unwrap(), ["lock", "add", "unlock"]);
+    // migrations added by target are rolled back
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM partial_target")
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+    assert!(
+        migrator
+            .fetch_lock_holder(&mut conn)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn lock_timeout() {
+    struct A;
+    migration!(A, "a", vec_box!(), vec_box!(), vec_box!());
+    let mut migrator = CustomMigrator {
+        lock_held_by_other: true,
+        ..CustomMigrator::default()
+    };
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let timeout = std::time::Duration::from_secs(1);
+    assert!(matches!(
+        migrator
+            .run(&mut conn, &Plan::apply_all().lock_timeout(Some(timeout)))
+            .await,
+        Err(Error::LockTimeout { timeout: error_timeout }) if error_timeout == timeout
+    ));
+    assert!(migrator.run(&mut conn, &Plan::apply_all()).await.is_ok());
+
+    // lock timeout of migrator is used unless plan overrides it
+    let holder = Migrator::<Sqlite>::default();
+    holder.lock(&mut conn).await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default().set_lock_timeout(std::time::Duration::ZERO);
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    assert_eq!(migrator.lock_timeout(), Some(std::time::Duration::ZERO));
+    assert!(matches!(
+        migrator.run(&mut conn, &Plan::apply_all()).await,
+        Err(Error::LockTimeout { timeout: error_timeout }) if error_timeout.is_zero()
+    ));
+    let plan_timeout = std::time::Duration::from_millis(10);
+    assert!(matches!(
+        migrator
+            .run(&mut conn, &Plan::apply_all().lock_timeout(Some(plan_timeout)))
+            .await,
+        Err(Error::LockTimeout { timeout: error_timeout }) if error_timeout == plan_timeout
+    ));
+    holder.unlock(&mut conn).await.unwrap();
+    assert!(migrator.run(&mut conn, &Plan::apply_all()).await.is_ok());
+}
+
+#[tokio::test]
+async fn sqlite_lock_across_connections() {
+    let path = std::env::temp_dir().join(format!("sqlx_migrator_lock_{}.db", std::process::id()));
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true);
+    let sqlite = SqlitePool::connect_with(options).await.unwrap();
+    let mut first_conn = sqlite.acquire().await.unwrap();
+    let mut second_conn = sqlite.acquire().await.unwrap();
+    let first_migrator = Migrator::<Sqlite>::default();
+    let second_migrator = Migrator::<Sqlite>::default();
+    let timeout = std::time::Duration::from_millis(250);
+
+    first_migrator.lock(&mut first_conn).await.unwrap();
+    assert!(matches!(
+        second_migrator
+            .lock_with_timeout(&mut second_conn, timeout)
+            .await,
+        Err(Error::LockTimeout { .. })
+    ));
+    // lock of other migrator of same process is not released by unlock
+    second_migrator.unlock(&mut second_conn).await.unwrap();
+    first_migrator.refresh_lock(&mut first_conn).await.unwrap();
+    first_migrator.unlock(&mut first_conn).await.unwrap();
+    second_migrator
+        .lock_with_timeout(&mut second_conn, timeout)
+        .await
+        .unwrap();
+    second_migrator.unlock(&mut second_conn).await.unwrap();
+
+    // lock which is not refreshed within expiry is taken over
+    first_migrator.lock(&mut first_conn).await.unwrap();
+    sqlx::query("UPDATE _sqlx_migrator_migrations_lock SET locked_at = datetime('now', '-1 hour')")
+        .execute(&mut *second_conn)
+        .await
+        .unwrap();
+    second_migrator
+        .lock_with_timeout(&mut second_conn, timeout)
+        .await
+        .unwrap();
+    assert!(matches!(
+        first_migrator.refresh_lock(&mut first_conn).await,
+        Err(Error::LockLost)
+    ));
+
+    // tables are not dropped while lock is held by other migrator and lock
+    // table is kept while lock is held by migrator itself
+    let lock_tables = async |conn: &mut sqlx::SqliteConnection| -> i64 {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = '_sqlx_migrator_migrations_lock'",
+        )
+        .fetch_one(conn)
+        .await
+        .unwrap()
+    };
+    assert!(matches!(
+        first_migrator
+            .drop_migration_table_if_exists(&mut first_conn)
+            .await,
+        Err(Error::LockHeld { .. })
+    ));
+    second_migrator
+        .drop_migration_table_if_exists(&mut second_conn)
+        .await
+        .unwrap();
+    assert_eq!(lock_tables(&mut second_conn).await, 1);
+    second_migrator.unlock(&mut second_conn).await.unwrap();
+    first_migrator
+        .drop_migration_table_if_exists(&mut first_conn)
+        .await
+        .unwrap();
+    assert_eq!(lock_tables(&mut second_conn).await, 0);
+
+    drop(first_conn);
+    drop(second_conn);
+    sqlite.close().await;
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn lock_released_on_error_and_panic() {
+    struct PanicOperation;
+    #[async_trait::async_trait]
+    impl Operation<Sqlite> for PanicOperation {
+        #[expect(clippy::panic)]
+        async fn up(
+            &self,
+            _connection: &mut <Sqlite as Database>::Connection,
+        ) -> Result<(), Error> {
+            panic!("operation panicked");
+        }
+    }
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("INVALID SQL", "DROP TABLE a"))
+        }
+    }
+    struct B;
+    impl Migration<Sqlite> for B {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "b"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(PanicOperation)
+        }
+    }
+    let sqlite = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let lock_count = |pool: SqlitePool| {
+        async move {
+            let (count,): (i64,) =
+                sqlx::query_as("SELECT COUNT(*) FROM _sqlx_migrator_migrations_lock")
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            count
+        }
+    };
+
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    assert!(migrator.run(&mut conn, &Plan::apply_all()).await.is_err());
+    drop(conn);
+    assert_eq!(lock_count(sqlite.clone()).await, 0);
+
+    let pool = sqlite.clone();
+    let result = tokio::spawn(async move {
+        let mut migrator = Migrator::<Sqlite>::default();
+        migrator.add_migrations(vec_box!(B)).unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        migrator.run(&mut conn, &Plan::apply_all()).await
+    })
+    .await;
+    assert!(result.unwrap_err().is_panic());
+    assert_eq!(lock_count(sqlite.clone()).await, 0);
+
+    let migrator = Migrator::<Sqlite>::default();
+    let mut guard = migrator
+        .acquire_lock(sqlite.acquire().await.unwrap(), None)
+        .await
+        .unwrap();
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM _sqlx_migrator_migrations_lock")
+        .fetch_one(&mut *guard)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    guard.release().await.unwrap();
+    assert_eq!(lock_count(sqlite).await, 0);
+}
+
+#[tokio::test]
+async fn lock_holder_and_force_unlock() {
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let migrator = Migrator::<Sqlite>::default();
+    let other_migrator = Migrator::<Sqlite>::default();
+
+    assert!(
+        migrator
+            .fetch_lock_holder(&mut conn)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(!migrator.force_unlock(&mut conn, "unknown").await.unwrap());
+    // inspecting lock do not create lock table
+    let (lock_table_count,) = sqlx::query_as::<_, (i64,)>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name = '_sqlx_migrator_migrations_lock'",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .unwrap();
+    assert_eq!(lock_table_count, 0);
+
+    migrator.lock(&mut conn).await.unwrap();
+    let holder = migrator
+        .fetch_lock_holder(&mut conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(holder.session(), super::sqlite::lock_owner(&migrator));
+    assert!(holder.since().is_some());
+    assert!(matches!(
+        other_migrator
+            .lock_with_timeout(&mut conn, std::time::Duration::ZERO)
+            .await,
+        Err(Error::LockTimeout { .. })
+    ));
+
+    // lock is only released when it is held by provided session
+    assert!(
+        !other_migrator
+            .force_unlock(&mut conn, &super::sqlite::lock_owner(&other_migrator))
+            .await
+            .unwrap()
+    );
+    assert!(
+        other_migrator
+            .force_unlock(&mut conn, holder.session())
+            .await
+            .unwrap()
+    );
+    assert!(
+        migrator
+            .fetch_lock_holder(&mut conn)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    other_migrator.lock(&mut conn).await.unwrap();
+    other_migrator.unlock(&mut conn).await.unwrap();
+}
+
+#[tokio::test]
+async fn partially_applied_non_atomic_migration() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(
+                ("CREATE TABLE a (id INTEGER)", "DROP TABLE a"),
+                ("CREATE TABLE b (id INTEGER)", "DROP TABLE b"),
+                ("INSERT INTO c VALUES (1)", "DELETE FROM c")
+            )
+        }
+
+        fn is_atomic(&self) -> bool {
+            false
+        }
+    }
+    async fn table_count(conn: &mut <Sqlite as Database>::Connection) -> i64 {
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('a', 'b')",
+        )
+        .fetch_one(conn)
+        .await
+        .unwrap();
+        count
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+
+    // failure of last operation leaves migration partially applied
+    assert!(migrator.run(&mut conn, &Plan::apply_all()).await.is_err());
+    let applied = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(applied.len(), 1);
+    assert!(applied[0].is_partially_applied());
+    assert_eq!(applied[0].applied_operations(), Some(2));
+    assert_eq!(table_count(&mut conn).await, 2);
+
+    // only applied operations are reverted
+    migrator.run(&mut conn, &Plan::revert_all()).await.unwrap();
+    assert!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(table_count(&mut conn).await, 0);
+
+    // partially applied migration is resumed from failed operation
+    assert!(migrator.run(&mut conn, &Plan::apply_all()).await.is_err());
+    let dry_run_migrations = migrator
+        .dry_run(&mut conn, &Plan::apply_all())
+        .await
+        .unwrap();
+    assert_eq!(
+        dry_run_migrations[0].statements(),
+        [Some("INSERT INTO c VALUES (1)".to_string())]
+    );
+    sqlx::query("CREATE TABLE c (id INTEGER)")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    let applied = migrator
+        .fetch_applied_migration_from_db(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(applied.len(), 1);
+    assert!(!applied[0].is_partially_applied());
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM c")
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn operation_failed_error() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(
+                ("CREATE TABLE a (id INTEGER)", "DROP TABLE a"),
+                ("INSERT INTO a VALUES (1)", "DELETE FROM missing")
+            )
+        }
+    }
+    struct B;
+    impl Migration<Sqlite> for B {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "b"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!(A)
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("INSERT INTO missing VALUES (1)", "DROP TABLE b"))
+        }
+
+        fn is_atomic(&self) -> bool {
+            false
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A, B)).unwrap();
+
+    let error = migrator
+        .run(&mut conn, &Plan::apply_all())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        &error,
+        Error::OperationFailed {
+            app,
+            name,
+            operation_index: 0,
+            direction: Direction::Apply,
+            rolled_back: false,
+            source,
+        } if app == "test" && name == "b" && matches!(**source, Error::Sqlx(_))
+    ));
+    assert_eq!(
+        error.to_string(),
+        "failed to apply operation 0 of migration test - b"
+    );
+
+    let error = migrator
+        .run(&mut conn, &Plan::revert_all())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Error::OperationFailed {
+            operation_index: 1,
+            direction: Direction::Revert,
+            rolled_back: true,
+            ..
+        }
+    ));
+    assert!(error.to_string().contains("(rolled back)"));
+}
+
+#[tokio::test]
+async fn continue_on_error() {
+    struct A1;
+    impl Migration<Sqlite> for A1 {
+        fn app(&self) -> &'static str {
+            "a"
+        }
+
+        fn name(&self) -> &'static str {
+            "a1"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("INSERT INTO missing VALUES (1)", "DELETE FROM missing"))
+        }
+
+        fn run_before(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!(C1)
+        }
+    }
+    struct A2;
+    impl Migration<Sqlite> for A2 {
+        fn app(&self) -> &'static str {
+            "a"
+        }
+
+        fn name(&self) -> &'static str {
+            "a2"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!(A1)
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE a2 (id INTEGER)", "DROP TABLE a2"))
+        }
+    }
+    struct B1;
+    impl Migration<Sqlite> for B1 {
+        fn app(&self) -> &'static str {
+            "b"
+        }
+
+        fn name(&self) -> &'static str {
+            "b1"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE b1 (id INTEGER)", "DROP TABLE b1"))
+        }
+    }
+    struct C1;
+    impl Migration<Sqlite> for C1 {
+        fn app(&self) -> &'static str {
+            "c"
+        }
+
+        fn name(&self) -> &'static str {
+            "c1"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE c1 (id INTEGER)", "DROP TABLE c1"))
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A1, A2, B1, C1)).unwrap();
+    let app_names = |list: &[(String, String)]| {
+        list.iter()
+            .map(|(app, name)| format!("{app}:{name}"))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>()
+    };
+
+    let report = migrator
+        .run_with_report(&mut conn, &Plan::apply_all().continue_on_error(true))
+        .await
+        .unwrap();
+    assert!(!report.is_success());
+    assert_eq!(app_names(report.succeeded()), ["b:b1"]);
+    assert!(matches!(
+        report.failed(),
+        [(app, name, Error::OperationFailed { .. })] if app == "a" && name == "a1"
+    ));
+    assert_eq!(app_names(report.skipped()), ["a:a2", "c:c1"]);
+
+    let result = migrator
+        .run(&mut conn, &Plan::apply_all().continue_on_error(true))
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::MigrationsFailed { report }) if report.skipped().len() == 2
+    ));
+}
+
+#[tokio::test]
+async fn single_transaction() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE a (id INTEGER)", "DROP TABLE a"))
+        }
+    }
+    struct B;
+    impl Migration<Sqlite> for B {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "b"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!(A)
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("INSERT INTO missing VALUES (1)", "DELETE FROM missing"))
+        }
+    }
+    struct C;
+    impl Migration<Sqlite> for C {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "c"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE c (id INTEGER)", "DROP TABLE c"))
+        }
+
+        fn is_atomic(&self) -> bool {
+            false
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A, B)).unwrap();
+
+    let error = migrator
+        .run(&mut conn, &Plan::apply_all().single_transaction(true))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::OperationFailed { .. }));
+    let table_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE name = 'a'")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+    assert_eq!(table_count, 0);
+    assert!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    migrator
+        .run(&mut conn, &Plan::apply_count(1).single_transaction(true))
+        .await
+        .unwrap();
+    assert_eq!(
+        migrator
+            .fetch_applied_migration_from_db(&mut conn)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A, C)).unwrap();
+    let result = migrator
+        .run(&mut conn, &Plan::apply_all().single_transaction(true))
+        .await;
+    assert!(matches!(result, Err(Error::PlanError { .. })));
+    let result = migrator
+        .run(
+            &mut conn,
+            &Plan::apply_all()
+                .single_transaction(true)
+                .continue_on_error(true),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::PlanError { .. })));
+}
+
+#[tokio::test]
+async fn operation_timeout() {
+    struct SlowOperation;
+    #[async_trait::async_trait]
+    impl Operation<Sqlite> for SlowOperation {
+        async fn up(&self, _connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            Ok(())
+        }
+
+        fn timeout(&self) -> Option<std::time::Duration> {
+            Some(std::time::Duration::from_millis(50))
+        }
+    }
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(SlowOperation)
+        }
+    }
+    let path =
+        std::env::temp_dir().join(format!("sqlx_migrator_timeout_{}.db", std::process::id()));
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true);
+    let sqlite = SqlitePool::connect_with(options).await.unwrap();
+    let mut first_conn = sqlite.acquire().await.unwrap();
+    let mut second_conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    let busy_timeout = async |conn: &mut sqlx::SqliteConnection| -> i64 {
+        sqlx::query_scalar("PRAGMA busy_timeout")
+            .fetch_one(conn)
+            .await
+            .unwrap()
+    };
+    let default_busy_timeout = busy_timeout(&mut first_conn).await;
+
+    // cancelled operation discards connection acquired from pool
+    let error = migrator
+        .run_with_pool(&sqlite, &Plan::apply_all())
+        .await
+        .unwrap_err();
+    assert!(error.is_connection_discarded());
+    assert!(matches!(
+        error,
+        Error::OperationFailed { source, .. }
+            if matches!(*source, Error::OperationCancelled { timeout }
+                if timeout == std::time::Duration::from_millis(50))
+    ));
+    // lock is not released using discarded connection
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM _sqlx_migrator_migrations_lock")
+        .fetch_one(&mut *first_conn)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(busy_timeout(&mut first_conn).await, default_busy_timeout);
+
+    // database lock wait exceeding busy timeout is reported as timeout error
+    let settings = migrator
+        .set_operation_timeout(&mut first_conn, std::time::Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert_eq!(busy_timeout(&mut first_conn).await, 100);
+    sqlx::query("BEGIN IMMEDIATE")
+        .execute(&mut *second_conn)
+        .await
+        .unwrap();
+    let error = Error::from(
+        sqlx::query("CREATE TABLE a (id INTEGER)")
+            .execute(&mut *first_conn)
+            .await
+            .unwrap_err(),
+    );
+    assert!(migrator.is_timeout_error(&error));
+    sqlx::query("ROLLBACK")
+        .execute(&mut *second_conn)
+        .await
+        .unwrap();
+    migrator
+        .restore_operation_timeout(&mut first_conn, settings)
+        .await
+        .unwrap();
+    assert_eq!(busy_timeout(&mut first_conn).await, default_busy_timeout);
+
+    drop(first_conn);
+    drop(second_conn);
+    sqlite.close().await;
+    std::fs::remove_file(path).unwrap();
+}
+
+/// Operation which fails with busy error on first attempt since other
+/// connection holds write lock of database
+struct BusyOnceOperation {
+    path: std::path::PathBuf,
+    attempts: Arc<AtomicUsize>,
+}
+#[async_trait::async_trait]
+impl Operation<Sqlite> for BusyOnceOperation {
+    async fn up(&self, connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+        if self.attempts.fetch_add(1, Ordering::SeqCst) > 0 {
+            sqlx::query("CREATE TABLE a (id INTEGER)")
+                .execute(connection)
+                .await?;
+            return Ok(());
+        }
+        let mut other_conn =
+            sqlx::SqliteConnection::connect(&format!("sqlite://{}", self.path.display())).await?;
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut other_conn)
+            .await?;
+        sqlx::query("PRAGMA busy_timeout = 0")
+            .execute(&mut *connection)
+            .await?;
+        let error = sqlx::query("CREATE TABLE a (id INTEGER)")
+            .execute(&mut *connection)
+            .await
+            .unwrap_err();
+        other_conn.close().await?;
+        Err(error.into())
+    }
+
+    async fn down(&self, connection: &mut sqlx::SqliteConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE a").execute(connection).await?;
+        Ok(())
+    }
+}
+/// Migration which runs busy once operation
+struct BusyOnceMigration {
+    path: std::path::PathBuf,
+    attempts: Arc<AtomicUsize>,
+    atomic: bool,
+}
+impl Migration<Sqlite> for BusyOnceMigration {
+    fn app(&self) -> &'static str {
+        "test"
+    }
+
+    fn name(&self) -> &'static str {
+        "a"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+        vec_box!()
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+        vec_box!(BusyOnceOperation {
+            path: self.path.clone(),
+            attempts: Arc::clone(&self.attempts),
+        })
+    }
+
+    fn is_atomic(&self) -> bool {
+        self.atomic
+    }
+}
+
+#[tokio::test]
+async fn retry_transient_error() {
+    let path = std::env::temp_dir().join(format!("sqlx_migrator_retry_{}.db", std::process::id()));
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true);
+    let sqlite = SqlitePool::connect_with(options).await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let policy = RetryPolicy::new(2).backoff(std::time::Duration::from_millis(10));
+    let migrator_with = |atomic: bool, policy: RetryPolicy| {
+        let mut migrator = Migrator::<Sqlite>::default().set_retry_policy(policy);
+        migrator
+            .add_migrations(vec_box!(BusyOnceMigration {
+                path: path.clone(),
+                attempts: Arc::clone(&attempts),
+                atomic,
+            }))
+            .unwrap();
+        migrator
+    };
+
+    let migrator = migrator_with(true, policy.clone());
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    migrator.run(&mut conn, &Plan::revert_all()).await.unwrap();
+
+    // non atomic migration and error which is not retried fails on first attempt
+    for (atomic, policy) in [
+        (false, policy.clone()),
+        (true, policy.retry_on(vec![TransientError::Deadlock])),
+    ] {
+        attempts.store(0, Ordering::SeqCst);
+        let result = migrator_with(atomic, policy)
+            .run(&mut conn, &Plan::apply_all())
+            .await;
+        assert!(matches!(result, Err(Error::OperationFailed { .. })));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    // atomic migration is also retried when database do not support
+    // transactional DDL
+    attempts.store(0, Ordering::SeqCst);
+    let mut migrator = CustomMigrator {
+        retry_policy: RetryPolicy::new(2),
+        ..CustomMigrator::default()
+    };
+    migrator
+        .add_migrations(vec_box!(BusyOnceMigration {
+            path: path.clone(),
+            attempts: Arc::clone(&attempts),
+            atomic: true,
+        }))
+        .unwrap();
+    assert!(
+        !migrator
+            .is_transactional_ddl_supported(&mut conn)
+            .await
+            .unwrap()
+    );
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+    drop(conn);
+    sqlite.close().await;
+    std::fs::remove_file(path).unwrap();
+}
+
+/// Listener which records events of migrator
+struct RecordListener(Arc<Mutex<Vec<String>>>);
+#[async_trait::async_trait]
+impl MigrationListener<Sqlite> for RecordListener {
+    async fn before_plan(
+        &self,
+        _connection: &mut sqlx::SqliteConnection,
+        direction: Direction,
+        migrations: &[&dyn Migration<Sqlite>],
+    ) -> Result<(), Error> {
+        let mut events = self.0.lock().unwrap();
+        events.push(format!(
+            "before_plan {} {}",
+            direction.as_str(),
+            migrations.len()
+        ));
+        Ok(())
+    }
+
+    async fn before_migration(
+        &self,
+        _connection: &mut sqlx::SqliteConnection,
+        migration: &dyn Migration<Sqlite>,
+        _direction: Direction,
+    ) -> Result<(), Error> {
+        let mut events = self.0.lock().unwrap();
+        events.push(format!("before_migration {}", migration.name()));
+        Ok(())
+    }
+
+    async fn after_operation(
+        &self,
+        _connection: &mut sqlx::SqliteConnection,
+        migration: &dyn Migration<Sqlite>,
+        _direction: Direction,
+        operation_index: usize,
+    ) -> Result<(), Error> {
+        let mut events = self.0.lock().unwrap();
+        events.push(format!(
+            "after_operation {} {operation_index}",
+            migration.name()
+        ));
+        Ok(())
+    }
+
+    async fn after_migration(
+        &self,
+        _connection: &mut sqlx::SqliteConnection,
+        migration: &dyn Migration<Sqlite>,
+        _direction: Direction,
+    ) -> Result<(), Error> {
+        let mut events = self.0.lock().unwrap();
+        events.push(format!("after_migration {}", migration.name()));
+        Ok(())
+    }
+
+    async fn on_error(
+        &self,
+        _connection: &mut sqlx::SqliteConnection,
+        migration: &dyn Migration<Sqlite>,
+        _direction: Direction,
+        _error: &Error,
+    ) {
+        let mut events = self.0.lock().unwrap();
+        events.push(format!("on_error {}", migration.name()));
+    }
+
+    async fn after_plan(
+        &self,
+        _connection: &mut sqlx::SqliteConnection,
+        _direction: Direction,
+        report: &RunReport,
+    ) -> Result<(), Error> {
+        let mut events = self.0.lock().unwrap();
+        events.push(format!("after_plan {}", report.failed().len()));
+        Ok(())
+    }
+}
+
+/// Listener which fails after migration is run
+struct FailingListener;
+#[async_trait::async_trait]
+impl MigrationListener<Sqlite> for FailingListener {
+    async fn after_migration(
+        &self,
+        _connection: &mut sqlx::SqliteConnection,
+        _migration: &dyn Migration<Sqlite>,
+        _direction: Direction,
+    ) -> Result<(), Error> {
+        Err(Error::IrreversibleOperation)
+    }
+}
+
+#[tokio::test]
+async fn migration_listener() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(
+                ("CREATE TABLE a (id INTEGER)", "DROP TABLE a"),
+                ("INSERT INTO a VALUES (1)", "DELETE FROM a")
+            )
+        }
+    }
+    struct B;
+    impl Migration<Sqlite> for B {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "b"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!(A)
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("INSERT INTO missing VALUES (1)", "DELETE FROM missing"))
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut migrator =
+        Migrator::<Sqlite>::default().add_listener(Box::new(RecordListener(Arc::clone(&events))));
+    migrator.add_migrations(vec_box!(A, B)).unwrap();
+
+    let report = migrator
+        .run_with_report(&mut conn, &Plan::apply_all().continue_on_error(true))
+        .await
+        .unwrap();
+    assert_eq!(report.failed().len(), 1);
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "before_plan apply 2",
+            "before_migration a",
+            "after_operation a 0",
+            "after_operation a 1",
+            "after_migration a",
+            "before_migration b",
+            "on_error b",
+            "after_plan 1",
+        ]
+    );
+
+    // failure of after migration listener do not fail committed migration
+    let mut migrator = Migrator::<Sqlite>::default().add_listener(Box::new(FailingListener));
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    migrator.run(&mut conn, &Plan::revert_all()).await.unwrap();
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+}
+
+/// Subscriber which records name and fields of spans
+#[derive(Default)]
+struct SpanRecorder(Mutex<Vec<(String, BTreeMap<String, String>)>>);
+
+/// Visitor which collects fields of span in map
+struct FieldVisitor<'map>(&'map mut BTreeMap<String, String>);
+
+impl tracing::field::Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(
+            field.name().to_string(),
+            format!("{value:?}").replace('"', ""),
+        );
+    }
+}
+
+impl tracing::Subscriber for SpanRecorder {
+    fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let mut spans = self.0.lock().unwrap();
+        let mut fields = BTreeMap::new();
+        attributes.record(&mut FieldVisitor(&mut fields));
+        spans.push((attributes.metadata().name().to_string(), fields));
+        tracing::span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+        let mut spans = self.0.lock().unwrap();
+        let index = usize::try_from(span.into_u64()).unwrap() - 1;
+        values.record(&mut FieldVisitor(&mut spans[index].1));
+    }
+
+    fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+    fn event(&self, _event: &tracing::Event<'_>) {}
+
+    fn enter(&self, _span: &tracing::span::Id) {}
+
+    fn exit(&self, _span: &tracing::span::Id) {}
+}
+
+#[tokio::test]
+async fn tracing_spans() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE a (id INTEGER)", "DROP TABLE a"))
+        }
+    }
+    let sqlite = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let mut conn = sqlite.acquire().await.unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A)).unwrap();
+    let recorder = Arc::new(SpanRecorder::default());
+    let dispatch = tracing::Dispatch::from(Arc::clone(&recorder));
+
+    let guard = tracing::dispatcher::set_default(&dispatch);
+    migrator.run(&mut conn, &Plan::apply_all()).await.unwrap();
+    drop(guard);
+
+    let spans = recorder.0.lock().unwrap();
+    let span_fields = |name: &str| {
+        spans
+            .iter()
+            .find(|(span_name, _)| span_name == name)
+            .map(|(_, fields)| fields.clone())
+            .unwrap()
+    };
+    let plan = span_fields("plan");
+    assert_eq!(plan["direction"], "apply");
+    assert_eq!(plan["migrations"], "1");
+    assert_eq!(plan["outcome"], "success");
+    assert!(plan.contains_key("duration_ms"));
+    let migration = span_fields("migration");
+    assert_eq!(migration["app"], "test");
+    assert_eq!(migration["name"], "a");
+    assert_eq!(migration["atomic"], "true");
+    assert_eq!(migration["fake"], "false");
+    assert_eq!(migration["attempts"], "1");
+    assert_eq!(migration["outcome"], "success");
+    let operation = span_fields("operation");
+    assert_eq!(operation["operation_index"], "0");
+    assert_eq!(operation["outcome"], "success");
+    assert!(operation.contains_key("duration_ms"));
+}
+
+#[tokio::test]
+async fn run_with_pool() {
+    struct A;
+    impl Migration<Sqlite> for A {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "a"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!()
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("CREATE TABLE a (id INTEGER)", "DROP TABLE a"))
+        }
+    }
+    struct B;
+    impl Migration<Sqlite> for B {
+        fn app(&self) -> &'static str {
+            "test"
+        }
+
+        fn name(&self) -> &'static str {
+            "b"
+        }
+
+        fn parents(&self) -> Vec<Box<dyn Migration<Sqlite>>> {
+            vec_box!(A)
+        }
+
+        fn operations(&self) -> Vec<Box<dyn Operation<Sqlite>>> {
+            vec_box!(("INSERT INTO missing VALUES (1)", "DELETE FROM missing"))
+        }
+    }
+    let sqlite = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let mut migrator = Migrator::<Sqlite>::default();
+    migrator.add_migrations(vec_box!(A, B)).unwrap();
+
+    let plan = migrator
+        .generate_migration_plan_with_pool(&sqlite, Some(&Plan::apply_all()))
+        .await
+        .unwrap();
+    assert_eq!(plan.len(), 2);
+    let report = migrator
+        .run_with_report_with_pool(&sqlite, &Plan::apply_all().continue_on_error(true))
+        .await
+        .unwrap();
+    assert_eq!(report.succeeded().len(), 1);
+    assert_eq!(report.failed().len(), 1);
+    assert!(matches!(
+        migrator.run_with_pool(&sqlite, &Plan::apply_all()).await,
+        Err(Error::OperationFailed { .. })
+    ));
+    migrator
+        .run_with_pool(&sqlite, &Plan::revert_all())
+        .await
+        .unwrap();
+
+    // connection is returned to pool with lock released
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM _sqlx_migrator_migrations_lock")
+        .fetch_one(&sqlite)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+    let applied = migrator
+        .generate_migration_plan_with_pool(&sqlite, Some(&Plan::revert_all()))
+        .await
+        .unwrap();
+    assert!(applied.is_empty());
+}
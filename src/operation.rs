@@ -40,6 +40,8 @@ impl Operation<Sqlite> for ExampleOperation {
 "
 )]
 
+use std::time::Duration;
+
 use sqlx::Database;
 
 use crate::error::Error;
@@ -83,6 +85,51 @@ where
     fn is_destructible(&self) -> bool {
         false
     }
+
+    /// Returns the SQL statement executed by the `up` method, if known.
+    ///
+    /// This is used to show statement which will be executed while doing dry
+    /// run of migrations. By default, statement is not known.
+    fn up_sql(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the SQL statement executed by the `down` method, if known.
+    ///
+    /// This is used to show statement which will be executed while doing dry
+    /// run of migrations. By default, statement is not known.
+    fn down_sql(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns a fingerprint which identifies the content of an operation.
+    ///
+    /// The fingerprint of all operations is used to calculate the checksum of a
+    /// migration, which is stored alongside applied migration and later used to
+    /// detect if an already applied migration was modified. By default,
+    /// operation do not have a fingerprint and such migration is never
+    /// checked.
+    fn fingerprint(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the timeout of the operation, if any.
+    ///
+    /// While operation runs with timeout, statement and lock wait timeout of
+    /// the connection are set to the timeout and operation is also cancelled
+    /// once timeout is elapsed. Cancelled operation can still be running on
+    /// database so its connection is discarded. By default, timeout of the
+    /// migration returned
+    /// by [`Migration::timeout`](crate::migration::Migration::timeout) is used.
+    ///
+    /// Mysql `max_execution_time` only applies to read only `SELECT` statement
+    /// so DDL statement is only bounded by `lock_wait_timeout` while waiting
+    /// for metadata lock. Once DDL statement acquires lock, it is not cancelled
+    /// by mysql and it keeps running on server even after operation is
+    /// cancelled and its connection is discarded.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
 }
 
 #[async_trait::async_trait]
@@ -109,4 +156,16 @@ where
             .map_err(Error::from)?;
         Ok(())
     }
+
+    fn up_sql(&self) -> Option<String> {
+        Some(self.0.as_ref().to_string())
+    }
+
+    fn down_sql(&self) -> Option<String> {
+        Some(self.1.as_ref().to_string())
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some(format!("up:{}\ndown:{}", self.0.as_ref(), self.1.as_ref()))
+    }
 }
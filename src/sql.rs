@@ -0,0 +1,379 @@
+//! Module for defining operation and migration using plain sql
+//!
+//! Migration can be loaded from a directory where each app contains migration
+//! directories with `up.sql` and an optional `down.sql` file
+//!
+//! ```text
+//! migrations
+//! ├── main
+//! │   ├── 0001_create_user
+//! │   │   ├── down.sql
+//! │   │   └── up.sql
+//! │   └── 0002_create_index
+//! │       └── up.sql
+//! └── other
+//!     └── 0001_create_post
+//!         ├── down.sql
+//!         └── up.sql
+//! ```
+//!
+//! Migrations of an app are sorted by numeric prefix of their name and then by
+//! their name so `2_create_post` comes before `10_create_comment`. Each
+//! migration has previous migration of same app as its parent. Additional
+//! metadata can be provided using comment directives at the start of `up.sql`
+//! file
+//!
+//! ```sql
+//! -- migrator:parent main/0001_create_user
+//! -- migrator:no-transaction
+//! -- migrator:destructible
+//! CREATE INDEX CONCURRENTLY post_user_id ON post (user_id);
+//! ```
+//!
+//! - `parent <app>/<name>` adds a parent migration, it can be repeated
+//! - `no-transaction` makes migration non atomic
+//! - `destructible` marks up operation as destructible
+//!
+//! SQL migrations can be mixed with rust migrations in a single migrator
+#![cfg_attr(
+    feature = "sqlite",
+    doc = "
+### Example
+```rust,no_run
+use sqlx_migrator::{Info, Migrator};
+use sqlx::Sqlite;
+
+# fn main() -> Result<(), sqlx_migrator::Error> {
+let mut migrator = Migrator::<Sqlite>::default();
+migrator.add_migrations(sqlx_migrator::sql::load_migrations(\"migrations\")?)?;
+# Ok(())
+# }
+```
+"
+)]
+
+use std::path::Path;
+
+use sqlx::{Database, Executor as _};
+
+use crate::error::Error;
+use crate::migration::Migration;
+use crate::operation::Operation;
+
+/// Prefix used by comment directive of sql migration
+const DIRECTIVE_PREFIX: &str = "migrator:";
+
+/// Operation which runs plain sql loaded from sql file
+///
+/// Sql can contain multiple statements which are executed without using
+/// prepared statement. If down sql is not present operation is irreversible.
+#[derive(Debug, Clone)]
+pub struct SqlFileOperation {
+    up: String,
+    down: Option<String>,
+    destructible: bool,
+}
+
+impl SqlFileOperation {
+    /// Create new sql operation from up sql and optional down sql
+    #[must_use]
+    pub fn new(up: impl Into<String>, down: Option<String>) -> Self {
+        Self {
+            up: up.into(),
+            down,
+            destructible: false,
+        }
+    }
+
+    /// Create new sql operation by reading up sql file and optional down sql
+    /// file
+    ///
+    /// # Errors
+    /// If sql file cannot be read
+    pub fn from_files(
+        up_path: impl AsRef<Path>,
+        down_path: Option<impl AsRef<Path>>,
+    ) -> Result<Self, Error> {
+        let up = std::fs::read_to_string(up_path)?;
+        let down = down_path.map(std::fs::read_to_string).transpose()?;
+        Ok(Self::new(up, down))
+    }
+
+    /// Set whether up sql is destructible or not
+    #[must_use]
+    pub fn set_destructible(mut self, destructible: bool) -> Self {
+        self.destructible = destructible;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB> Operation<DB> for SqlFileOperation
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    async fn up(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
+        connection.execute(sqlx::raw_sql(&self.up)).await?;
+        Ok(())
+    }
+
+    async fn down(&self, connection: &mut <DB as Database>::Connection) -> Result<(), Error> {
+        let Some(down) = &self.down else {
+            return Err(Error::IrreversibleOperation);
+        };
+        connection.execute(sqlx::raw_sql(down)).await?;
+        Ok(())
+    }
+
+    fn is_destructible(&self) -> bool {
+        self.destructible
+    }
+
+    fn up_sql(&self) -> Option<String> {
+        Some(self.up.clone())
+    }
+
+    fn down_sql(&self) -> Option<String> {
+        self.down.clone()
+    }
+
+    fn fingerprint(&self) -> Option<String> {
+        Some(format!(
+            "up:{}\ndown:{}",
+            self.up,
+            self.down.as_deref().unwrap_or_default()
+        ))
+    }
+}
+
+/// Migration which is created from plain sql
+///
+/// Metadata of migration is parsed from comment directives present at the
+/// start of up sql
+#[derive(Debug, Clone)]
+pub struct SqlMigration {
+    app: String,
+    name: String,
+    parents: Vec<(String, String)>,
+    atomic: bool,
+    operation: SqlFileOperation,
+}
+
+impl SqlMigration {
+    /// Create new sql migration from up sql and optional down sql
+    ///
+    /// # Errors
+    /// If comment directive present in up sql is invalid
+    pub fn new(
+        app: impl Into<String>,
+        name: impl Into<String>,
+        up: impl Into<String>,
+        down: Option<String>,
+    ) -> Result<Self, Error> {
+        let app = app.into();
+        let name = name.into();
+        let up = up.into();
+        let mut parents = vec![];
+        let mut atomic = true;
+        let mut destructible = false;
+        for line in up.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            // directives are only read from leading comment lines
+            let Some(comment) = line.strip_prefix("--") else {
+                break;
+            };
+            let Some(directive) = comment.trim().strip_prefix(DIRECTIVE_PREFIX) else {
+                continue;
+            };
+            let directive = directive.trim();
+            let (key, value) = directive
+                .split_once(char::is_whitespace)
+                .map_or((directive, ""), |(key, value)| (key, value.trim()));
+            match (key, value) {
+                ("parent", parent) => {
+                    let Some((parent_app, parent_name)) = parent.split_once('/') else {
+                        return Err(Error::InvalidSqlMigration {
+                            app,
+                            name,
+                            message: format!("parent {parent} is not in format <app>/<name>"),
+                        });
+                    };
+                    parents.push((parent_app.to_string(), parent_name.to_string()));
+                }
+                ("no-transaction", "") => atomic = false,
+                ("destructible", "") => destructible = true,
+                _ => {
+                    return Err(Error::InvalidSqlMigration {
+                        app,
+                        name,
+                        message: format!("unknown directive {directive}"),
+                    });
+                }
+            }
+        }
+        Ok(Self {
+            app,
+            name,
+            parents,
+            atomic,
+            operation: SqlFileOperation::new(up, down).set_destructible(destructible),
+        })
+    }
+
+    /// Create new sql migration from operation without parsing comment
+    /// directives. Migration is atomic and has no parent by default
+    #[must_use]
+    pub fn from_operation(
+        app: impl Into<String>,
+        name: impl Into<String>,
+        operation: SqlFileOperation,
+    ) -> Self {
+        Self {
+            app: app.into(),
+            name: name.into(),
+            parents: vec![],
+            atomic: true,
+            operation,
+        }
+    }
+
+    /// Set whether migration is atomic or not
+    #[must_use]
+    pub fn set_atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Add parent migration
+    #[must_use]
+    pub fn add_parent(mut self, app: impl Into<String>, name: impl Into<String>) -> Self {
+        let parent = (app.into(), name.into());
+        if !self.parents.contains(&parent) {
+            self.parents.push(parent);
+        }
+        self
+    }
+}
+
+impl<DB> Migration<DB> for SqlMigration
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    fn app(&self) -> &str {
+        &self.app
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<DB>>> {
+        self.parents
+            .iter()
+            .map(|parent| Box::new(parent.clone()) as Box<dyn Migration<DB>>)
+            .collect()
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<DB>>> {
+        vec![Box::new(self.operation.clone())]
+    }
+
+    fn is_atomic(&self) -> bool {
+        self.atomic
+    }
+}
+
+/// Key used for sorting migrations of an app. Numeric prefix of name is
+/// compared as number so migration names do not need to be zero padded
+fn name_order(name: &str) -> (usize, &str, &str) {
+    let digits = name
+        .find(|character: char| !character.is_ascii_digit())
+        .map_or(name, |index| &name[..index]);
+    let number = digits.trim_start_matches('0');
+    (number.len(), number, name)
+}
+
+/// Link sql migrations so that each migration has previous migration of same
+/// app as its parent. Migrations are sorted by app and then by numeric prefix
+/// of name and name before linking.
+#[must_use]
+pub fn link_migrations<DB>(mut migrations: Vec<SqlMigration>) -> Vec<Box<dyn Migration<DB>>>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    migrations.sort_by(|a, b| (&a.app, name_order(&a.name)).cmp(&(&b.app, name_order(&b.name))));
+    let mut linked_migrations: Vec<Box<dyn Migration<DB>>> = Vec::with_capacity(migrations.len());
+    let mut previous: Option<(String, String)> = None;
+    for migration in migrations {
+        let migration = match previous {
+            Some((previous_app, previous_name)) if previous_app == migration.app => {
+                migration.add_parent(previous_app, previous_name)
+            }
+            _ => migration,
+        };
+        previous = Some((migration.app.clone(), migration.name.clone()));
+        linked_migrations.push(Box::new(migration));
+    }
+    linked_migrations
+}
+
+/// Load sql migrations from directory
+///
+/// Directory should contain app directories which contain migration
+/// directories having `up.sql` and optional `down.sql` file. Migration
+/// directories without `up.sql` file are ignored.
+///
+/// # Errors
+/// If directory cannot be read or any sql migration is invalid
+pub fn load_migrations<DB>(path: impl AsRef<Path>) -> Result<Vec<Box<dyn Migration<DB>>>, Error>
+where
+    DB: Database,
+    for<'c> &'c mut <DB as Database>::Connection: sqlx::Executor<'c, Database = DB>,
+{
+    let mut migrations = vec![];
+    for app_entry in std::fs::read_dir(path)? {
+        let app_path = app_entry?.path();
+        if !app_path.is_dir() {
+            continue;
+        }
+        let app = directory_name(&app_path)?;
+        for migration_entry in std::fs::read_dir(&app_path)? {
+            let migration_path = migration_entry?.path();
+            let up_path = migration_path.join("up.sql");
+            if !migration_path.is_dir() || !up_path.is_file() {
+                continue;
+            }
+            let down_path = migration_path.join("down.sql");
+            let down = if down_path.is_file() {
+                Some(std::fs::read_to_string(down_path)?)
+            } else {
+                None
+            };
+            migrations.push(SqlMigration::new(
+                app.clone(),
+                directory_name(&migration_path)?,
+                std::fs::read_to_string(up_path)?,
+                down,
+            )?);
+        }
+    }
+    Ok(link_migrations(migrations))
+}
+
+/// Get name of directory as utf-8 string
+fn directory_name(path: &Path) -> Result<String, Error> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(ToString::to_string)
+        .ok_or_else(|| {
+            Error::StdIo(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is not valid utf-8 directory name", path.display()),
+            ))
+        })
+}
@@ -1,11 +1,18 @@
 //! Module which is used to sync a external migration schema to `sqlx_migrator`
 //! sqlx migration
 
-use sqlx::Database;
+use sqlx::{Connection as _, Database};
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+use sqlx::{Executor, IntoArguments};
 
-use crate::migrator::DatabaseOperation;
+use crate::migration::HistoryEvent;
+use crate::migrator::{BorrowedConnection, DatabaseOperation, LockGuard, catch_unwind};
 use crate::{Error, Info, Migration, Migrator};
 
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+#[cfg(test)]
+mod tests;
+
 /// Trait which is implemented for syncing a migration from old migrator to
 /// new migrator
 #[async_trait::async_trait]
@@ -18,6 +25,24 @@ where
         &self,
         connection: &mut <DB as Database>::Connection,
     ) -> Result<Vec<Box<dyn Migration<DB>>>, Error>;
+
+    /// Returns a list of applied migrations from the old migrator along with
+    /// time when migration was applied
+    ///
+    /// Applied time is written to migration table while syncing so original
+    /// timestamp is preserved. By default, applied time is not known and
+    /// current time is used while syncing
+    async fn applied_migrations_with_time(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Vec<AppliedOldMigration<DB>>, Error> {
+        Ok(self
+            .applied_migrations(connection)
+            .await?
+            .into_iter()
+            .map(|migration| (migration, None))
+            .collect())
+    }
 }
 
 #[async_trait::async_trait]
@@ -32,6 +57,9 @@ where
     ) -> Result<Vec<Box<dyn Migration<DB>>>, Error> {
         let mut applied_list: Vec<Box<dyn Migration<DB>>> = vec![];
         for migration in self.fetch_applied_migration_from_db(connection).await? {
+            if migration.is_partially_applied() {
+                continue;
+            }
             applied_list.push(Box::new((
                 migration.app().to_string(),
                 migration.name().to_string(),
@@ -39,6 +67,594 @@ where
         }
         Ok(applied_list)
     }
+
+    async fn applied_migrations_with_time(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<Vec<AppliedOldMigration<DB>>, Error> {
+        let mut applied_list: Vec<AppliedOldMigration<DB>> = vec![];
+        for migration in self.fetch_applied_migration_from_db(connection).await? {
+            if migration.is_partially_applied() {
+                continue;
+            }
+            applied_list.push((
+                Box::new((migration.app().to_string(), migration.name().to_string())),
+                Some(migration.applied_time().to_string()),
+            ));
+        }
+        Ok(applied_list)
+    }
+}
+
+/// Migration applied by old migrator along with time when it was applied if
+/// time is known
+pub type AppliedOldMigration<DB> = (Box<dyn Migration<DB>>, Option<String>);
+
+/// Function which maps version and description of old migration to app and
+/// name of migration. Migration mapped to `None` is ignored
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+type VersionMapper = Box<dyn Fn(i64, &str) -> Option<(String, String)> + Send + Sync>;
+
+/// Function which maps version of old migration to app and name of migration.
+/// Migration mapped to `None` is ignored
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+type VersionOnlyMapper = Box<dyn Fn(i64) -> Option<(String, String)> + Send + Sync>;
+
+/// Function which maps textual version of old migration to app and name of
+/// migration. Migration mapped to `None` is ignored
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+type TextVersionMapper = Box<dyn Fn(&str) -> Option<(String, String)> + Send + Sync>;
+
+/// Old migrator which reads migrations applied by sqlx native migrator from
+/// `_sqlx_migrations` table
+///
+/// Only successfully applied migrations are returned and each of them is mapped
+/// to app and name of migration using provided mapper function
+///
+/// ```rust
+/// use sqlx_migrator::sync::SqlxNativeMigrator;
+///
+/// let old_migrator = SqlxNativeMigrator::new(|version, description| {
+///     Some(("main".to_string(), format!("{version}_{description}")))
+/// });
+/// ```
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub struct SqlxNativeMigrator {
+    table_name: String,
+    mapper: VersionMapper,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl SqlxNativeMigrator {
+    /// Create new sqlx native migrator with mapper function which maps version
+    /// and description of sqlx migration to app and name of migration
+    pub fn new<F>(mapper: F) -> Self
+    where
+        F: Fn(i64, &str) -> Option<(String, String)> + Send + Sync + 'static,
+    {
+        Self {
+            table_name: "_sqlx_migrations".to_string(),
+            mapper: Box::new(mapper),
+        }
+    }
+
+    /// Set table name used by sqlx native migrator. By default
+    /// `_sqlx_migrations` table is used
+    #[must_use]
+    pub fn set_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = table_name.into();
+        self
+    }
+
+    async fn fetch_applied_migrations<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        backend_name: &str,
+    ) -> Result<Vec<AppliedOldMigration<DB>>, Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'r> (i64, String, Option<String>): sqlx::FromRow<'r, <DB as Database>::Row>,
+    {
+        let query = format!(
+            "SELECT version, description, {} FROM {} WHERE success ORDER BY version",
+            timestamp_as_text(backend_name, "installed_on"),
+            self.table_name
+        );
+        let rows = sqlx::query_as::<_, (i64, String, Option<String>)>(&query)
+            .fetch_all(connection)
+            .await?;
+        let mut applied_list: Vec<AppliedOldMigration<DB>> = vec![];
+        for (version, description, installed_on) in rows {
+            if let Some(app_name) = (self.mapper)(version, &description) {
+                applied_list.push((Box::new(app_name), installed_on));
+            }
+        }
+        Ok(applied_list)
+    }
+}
+
+/// Old migrator which reads migrations applied by refinery from
+/// `refinery_schema_history` table
+///
+/// Each migration is mapped to app and name of migration using provided mapper
+/// function which receives version and name of refinery migration
+///
+/// ```rust
+/// use sqlx_migrator::sync::RefineryMigrator;
+///
+/// let old_migrator = RefineryMigrator::new(|version, name| {
+///     Some(("main".to_string(), format!("V{version}__{name}")))
+/// });
+/// ```
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub struct RefineryMigrator {
+    table_name: String,
+    mapper: VersionMapper,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl RefineryMigrator {
+    /// Create new refinery migrator with mapper function which maps version and
+    /// name of refinery migration to app and name of migration
+    pub fn new<F>(mapper: F) -> Self
+    where
+        F: Fn(i64, &str) -> Option<(String, String)> + Send + Sync + 'static,
+    {
+        Self {
+            table_name: "refinery_schema_history".to_string(),
+            mapper: Box::new(mapper),
+        }
+    }
+
+    /// Set table name used by refinery. By default `refinery_schema_history`
+    /// table is used
+    #[must_use]
+    pub fn set_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = table_name.into();
+        self
+    }
+
+    async fn fetch_applied_migrations<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        _backend_name: &str,
+    ) -> Result<Vec<AppliedOldMigration<DB>>, Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'r> (i32, String, Option<String>): sqlx::FromRow<'r, <DB as Database>::Row>,
+    {
+        // refinery stores applied time as RFC 3339 text so it is normalised
+        // to format used by migration table
+        let query = format!(
+            "SELECT version, name, applied_on FROM {} ORDER BY version",
+            self.table_name
+        );
+        let rows = sqlx::query_as::<_, (i32, String, Option<String>)>(&query)
+            .fetch_all(connection)
+            .await?;
+        let mut applied_list: Vec<AppliedOldMigration<DB>> = vec![];
+        for (version, name, applied_on) in rows {
+            if let Some(app_name) = (self.mapper)(i64::from(version), &name) {
+                applied_list.push((
+                    Box::new(app_name),
+                    applied_on.as_deref().map(rfc3339_to_utc),
+                ));
+            }
+        }
+        Ok(applied_list)
+    }
+}
+
+/// Old migrator which reads migrations applied by diesel from
+/// `__diesel_schema_migrations` table
+///
+/// Each migration is mapped to app and name of migration using provided mapper
+/// function which receives version of diesel migration
+///
+/// ```rust
+/// use sqlx_migrator::sync::DieselMigrator;
+///
+/// let old_migrator =
+///     DieselMigrator::new(|version| Some(("main".to_string(), version.to_string())));
+/// ```
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub struct DieselMigrator {
+    table_name: String,
+    mapper: TextVersionMapper,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl DieselMigrator {
+    /// Create new diesel migrator with mapper function which maps version of
+    /// diesel migration to app and name of migration
+    pub fn new<F>(mapper: F) -> Self
+    where
+        F: Fn(&str) -> Option<(String, String)> + Send + Sync + 'static,
+    {
+        Self {
+            table_name: "__diesel_schema_migrations".to_string(),
+            mapper: Box::new(mapper),
+        }
+    }
+
+    /// Set table name used by diesel. By default `__diesel_schema_migrations`
+    /// table is used
+    #[must_use]
+    pub fn set_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = table_name.into();
+        self
+    }
+
+    async fn fetch_applied_migrations<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        backend_name: &str,
+    ) -> Result<Vec<AppliedOldMigration<DB>>, Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'r> (String, Option<String>): sqlx::FromRow<'r, <DB as Database>::Row>,
+    {
+        let query = format!(
+            "SELECT version, {} FROM {} ORDER BY version",
+            timestamp_as_text(backend_name, "run_on"),
+            self.table_name
+        );
+        let rows = sqlx::query_as::<_, (String, Option<String>)>(&query)
+            .fetch_all(connection)
+            .await?;
+        let mut applied_list: Vec<AppliedOldMigration<DB>> = vec![];
+        for (version, run_on) in rows {
+            if let Some(app_name) = (self.mapper)(&version) {
+                applied_list.push((Box::new(app_name), run_on));
+            }
+        }
+        Ok(applied_list)
+    }
+}
+
+/// Old migrator which reads migrations applied by golang-migrate from
+/// `schema_migrations` table
+///
+/// golang-migrate only stores version of last applied migration so list of all
+/// known versions is required. All known versions which are less than or equal
+/// to stored version are considered as applied and are mapped to app and name
+/// of migration using provided mapper function. If stored version is dirty
+/// [`Error::DirtyOldMigration`] is returned
+///
+/// ```rust
+/// use sqlx_migrator::sync::GolangMigrateMigrator;
+///
+/// let old_migrator = GolangMigrateMigrator::new([1, 2, 3], |version| {
+///     Some(("main".to_string(), version.to_string()))
+/// });
+/// ```
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub struct GolangMigrateMigrator {
+    table_name: String,
+    versions: Vec<i64>,
+    mapper: VersionOnlyMapper,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl GolangMigrateMigrator {
+    /// Create new golang-migrate migrator with list of all known versions and
+    /// mapper function which maps version of golang-migrate migration to app
+    /// and name of migration
+    pub fn new<F>(versions: impl IntoIterator<Item = i64>, mapper: F) -> Self
+    where
+        F: Fn(i64) -> Option<(String, String)> + Send + Sync + 'static,
+    {
+        let mut versions = versions.into_iter().collect::<Vec<_>>();
+        versions.sort_unstable();
+        Self {
+            table_name: "schema_migrations".to_string(),
+            versions,
+            mapper: Box::new(mapper),
+        }
+    }
+
+    /// Set table name used by golang-migrate. By default `schema_migrations`
+    /// table is used
+    #[must_use]
+    pub fn set_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = table_name.into();
+        self
+    }
+
+    async fn fetch_applied_migrations<DB>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        _backend_name: &str,
+    ) -> Result<Vec<AppliedOldMigration<DB>>, Error>
+    where
+        DB: Database,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'r> (i64, bool): sqlx::FromRow<'r, <DB as Database>::Row>,
+    {
+        // golang-migrate do not store applied time of migration
+        let query = format!("SELECT version, dirty FROM {}", self.table_name);
+        let row = sqlx::query_as::<_, (i64, bool)>(&query)
+            .fetch_optional(connection)
+            .await?;
+        let mut applied_list: Vec<AppliedOldMigration<DB>> = vec![];
+        if let Some((current_version, dirty)) = row {
+            if dirty {
+                return Err(Error::DirtyOldMigration {
+                    version: current_version.to_string(),
+                });
+            }
+            for &version in &self.versions {
+                if version > current_version {
+                    break;
+                }
+                if let Some(app_name) = (self.mapper)(version) {
+                    applied_list.push((Box::new(app_name), None));
+                }
+            }
+        }
+        Ok(applied_list)
+    }
+}
+
+/// Returns expression which selects timestamp column as text for database
+/// backend
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+fn timestamp_as_text(backend_name: &str, column: &str) -> String {
+    match backend_name {
+        #[cfg(feature = "postgres")]
+        <sqlx::Postgres as Database>::NAME => format!("{column}::TEXT"),
+        #[cfg(feature = "mysql")]
+        <sqlx::MySql as Database>::NAME => format!("DATE_FORMAT({column}, '%Y-%m-%d %H:%i:%s')"),
+        _ => column.to_string(),
+    }
+}
+
+/// Convert RFC 3339 timestamp such as `2024-01-02T03:04:05.123+05:30` to UTC
+/// timestamp in `YYYY-MM-DD HH:MM:SS` format which is understood by all
+/// supported databases. Timestamp which cannot be parsed is returned as it is
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+fn rfc3339_to_utc(timestamp: &str) -> String {
+    parse_rfc3339(timestamp).map_or_else(
+        || timestamp.to_string(),
+        |seconds| {
+            let days = seconds.div_euclid(86400);
+            let day_seconds = seconds.rem_euclid(86400);
+            let (year, month, day) = civil_from_days(days);
+            format!(
+                "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+                day_seconds / 3600,
+                day_seconds % 3600 / 60,
+                day_seconds % 60
+            )
+        },
+    )
+}
+
+/// Parse RFC 3339 timestamp to seconds since unix epoch. Fraction of second is
+/// ignored. Invalid date or time such as `2023-02-29` or leap second is not
+/// parsed
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+fn parse_rfc3339(timestamp: &str) -> Option<i64> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = timestamp.get(range)?;
+        if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let bytes = timestamp.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    let date = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    let days = days_from_civil(date.0, date.1, date.2);
+    // converting days back to date detects out of range month or day
+    if civil_from_days(days) != date || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let seconds = hour * 3600 + minute * 60 + second;
+    let offset = match timestamp.get(19..)?.strip_prefix('.') {
+        Some(fraction) => {
+            let offset = fraction.trim_start_matches(|character: char| character.is_ascii_digit());
+            // fraction of second requires at least one digit
+            if offset.len() == fraction.len() {
+                return None;
+            }
+            offset
+        }
+        None => &timestamp[19..],
+    };
+    let offset_seconds = match offset.as_bytes() {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let offset_start = timestamp.len() - offset.len();
+            let (offset_hour, offset_minute) = (
+                number(offset_start + 1..offset_start + 3)?,
+                number(offset_start + 4..offset_start + 6)?,
+            );
+            if offset_hour > 23 || offset_minute > 59 {
+                return None;
+            }
+            let offset_seconds = offset_hour * 3600 + offset_minute * 60;
+            if *sign == b'-' {
+                -offset_seconds
+            } else {
+                offset_seconds
+            }
+        }
+        _ => return None,
+    };
+    Some(days * 86400 + seconds - offset_seconds)
+}
+
+/// Returns number of days since unix epoch for date of proleptic gregorian
+/// calendar
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns year, month and day of proleptic gregorian calendar for number of
+/// days since unix epoch
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Trait which returns name of database backend of connection
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+pub(crate) trait ConnectionBackend {
+    /// Returns backend name of connection
+    fn backend(&self) -> &str;
+}
+
+#[cfg(feature = "postgres")]
+impl ConnectionBackend for sqlx::PgConnection {
+    fn backend(&self) -> &str {
+        <sqlx::Postgres as Database>::NAME
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ConnectionBackend for sqlx::SqliteConnection {
+    fn backend(&self) -> &str {
+        <sqlx::Sqlite as Database>::NAME
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl ConnectionBackend for sqlx::MySqlConnection {
+    fn backend(&self) -> &str {
+        <sqlx::MySql as Database>::NAME
+    }
+}
+
+#[cfg(all(
+    any(feature = "postgres", feature = "mysql", feature = "sqlite"),
+    feature = "any"
+))]
+impl ConnectionBackend for sqlx::AnyConnection {
+    fn backend(&self) -> &str {
+        self.backend_name()
+    }
+}
+
+/// Implement [`OldMigrator`] for supported databases using
+/// `fetch_applied_migrations` method of old migrator
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+macro_rules! impl_old_migrator {
+    ($migrator:ty) => {
+        #[cfg(feature = "postgres")]
+        impl_old_migrator!($migrator, sqlx::Postgres);
+        #[cfg(feature = "sqlite")]
+        impl_old_migrator!($migrator, sqlx::Sqlite);
+        #[cfg(feature = "mysql")]
+        impl_old_migrator!($migrator, sqlx::MySql);
+        #[cfg(all(
+            any(feature = "postgres", feature = "mysql", feature = "sqlite"),
+            feature = "any"
+        ))]
+        impl_old_migrator!($migrator, sqlx::Any);
+    };
+    ($migrator:ty, $db:ty) => {
+        #[async_trait::async_trait]
+        impl OldMigrator<$db> for $migrator {
+            async fn applied_migrations(
+                &self,
+                connection: &mut <$db as Database>::Connection,
+            ) -> Result<Vec<Box<dyn Migration<$db>>>, Error> {
+                Ok(self
+                    .applied_migrations_with_time(connection)
+                    .await?
+                    .into_iter()
+                    .map(|(migration, _)| migration)
+                    .collect())
+            }
+
+            async fn applied_migrations_with_time(
+                &self,
+                connection: &mut <$db as Database>::Connection,
+            ) -> Result<Vec<AppliedOldMigration<$db>>, Error> {
+                let backend_name = connection.backend().to_string();
+                self.fetch_applied_migrations::<$db>(connection, &backend_name)
+                    .await
+            }
+        }
+    };
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl_old_migrator!(SqlxNativeMigrator);
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl_old_migrator!(RefineryMigrator);
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl_old_migrator!(DieselMigrator);
+#[cfg(any(feature = "postgres", feature = "mysql", feature = "sqlite"))]
+impl_old_migrator!(GolangMigrateMigrator);
+
+/// Report of sync which lists migrations applied by old migrator according to
+/// how they were handled
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    inserted: Vec<(String, String)>,
+    already_applied: Vec<(String, String)>,
+    unknown: Vec<(String, String)>,
+}
+
+impl SyncReport {
+    /// Returns app and name of migrations which are added to migration table.
+    /// For dry run these migrations would have been added
+    #[must_use]
+    pub fn inserted(&self) -> &[(String, String)] {
+        &self.inserted
+    }
+
+    /// Returns app and name of migrations which are already present in
+    /// migration table
+    #[must_use]
+    pub fn already_applied(&self) -> &[(String, String)] {
+        &self.already_applied
+    }
+
+    /// Returns app and name of migrations which are not present in migration
+    /// list of migrator so they are skipped
+    #[must_use]
+    pub fn unknown(&self) -> &[(String, String)] {
+        &self.unknown
+    }
 }
 
 /// Trait which is implemented for syncing a migration from old migrator to new
@@ -55,8 +671,14 @@ where
     /// 1. Migrating from external migrations to`sqlx_migrator` migration
     /// 2. Handling table renames
     ///
-    /// Note: Migration timestamps are not preserved - the current time is used
-    /// for entries
+    /// Applied time returned by
+    /// [`OldMigrator::applied_migrations_with_time`] is preserved, if old
+    /// migrator do not know applied time current time is used. Migrations are
+    /// added inside single transaction. Returns report
+    /// which lists inserted, already applied and unknown migrations. Similar
+    /// to [`Migrate::run`](crate::migrator::Migrate::run), if returned future
+    /// is cancelled migration lock remains held by session of connection till
+    /// connection is closed
     ///
     /// # Errors
     /// If sync cannot be completed
@@ -64,36 +686,27 @@ where
         &self,
         connection: &mut <DB as Database>::Connection,
         old_migrator: &O,
-    ) -> Result<(), Error>
+    ) -> Result<SyncReport, Error>
     where
-        O: OldMigrator<DB>,
+        O: OldMigrator<DB> + ?Sized,
     {
-        tracing::debug!("syncing old migrator");
-        self.lock(connection).await?;
-        // Use a block to ensure the lock is released before returning
-        let result = async {
-            let old_migrator_applied_migrations =
-                old_migrator.applied_migrations(connection).await?;
-            let already_applied_migration =
-                self.fetch_applied_migration_from_db(connection).await?;
-            let full_migration_list = self.migrations();
-            for migration in old_migrator_applied_migrations {
-                // Only add migration if it exists in the full migration list
-                // and has not already been applied in the new migrator
-                if full_migration_list.contains(&migration)
-                    && !already_applied_migration
-                        .iter()
-                        .any(|applied| applied == &migration)
-                {
-                    self.add_migration_to_db_table(connection, &migration)
-                        .await?;
-                }
-            }
-            Ok(())
-        }
-        .await;
-        self.unlock(connection).await?;
-        result
+        sync_old_migrator(self, connection, old_migrator, false).await
+    }
+
+    /// Generates report of sync without adding any migration to migration
+    /// table. Migration table is created if it do not exists
+    ///
+    /// # Errors
+    /// If applied migrations cannot be fetched
+    async fn sync_dry_run<O>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        old_migrator: &O,
+    ) -> Result<SyncReport, Error>
+    where
+        O: OldMigrator<DB> + ?Sized,
+    {
+        sync_old_migrator(self, connection, old_migrator, true).await
     }
 }
 
@@ -103,3 +716,100 @@ where
     Self: DatabaseOperation<DB>,
 {
 }
+
+/// Sync applied migrations of old migrator to migrator. Migration table is
+/// only modified when it is not dry run
+pub(crate) async fn sync_old_migrator<DB, M, O>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    old_migrator: &O,
+    dry_run: bool,
+) -> Result<SyncReport, Error>
+where
+    DB: Database,
+    M: Info<DB> + DatabaseOperation<DB> + Sync + ?Sized,
+    O: OldMigrator<DB> + ?Sized,
+{
+    tracing::debug!("syncing old migrator");
+    if dry_run {
+        // migration table is required for finding already applied migrations
+        migrator.ensure_migration_table_exists(connection).await?;
+        return sync_report(migrator, connection, old_migrator, true).await;
+    }
+    let mut guard = LockGuard::acquire(migrator, BorrowedConnection(connection), None).await?;
+    // Result is returned after unlocking so lock is released even on failure or
+    // panic
+    let result = catch_unwind(async {
+        // migrations are added inside single transaction so that sync is either
+        // completed or not done at all
+        let mut transaction = guard.begin().await?;
+        let report = sync_report(migrator, &mut transaction, old_migrator, false).await?;
+        transaction.commit().await?;
+        Ok(report)
+    })
+    .await;
+    guard.release_unwind(result).await
+}
+
+/// Create sync report and add migrations to migration table if it is not dry
+/// run
+async fn sync_report<DB, M, O>(
+    migrator: &M,
+    connection: &mut <DB as Database>::Connection,
+    old_migrator: &O,
+    dry_run: bool,
+) -> Result<SyncReport, Error>
+where
+    DB: Database,
+    M: Info<DB> + DatabaseOperation<DB> + Sync + ?Sized,
+    O: OldMigrator<DB> + ?Sized,
+{
+    let old_migrator_applied_migrations = old_migrator
+        .applied_migrations_with_time(connection)
+        .await?;
+    let already_applied_migration = migrator.fetch_applied_migration_from_db(connection).await?;
+    let full_migration_list = migrator.migrations();
+    let mut report = SyncReport::default();
+    for (migration, applied_time) in old_migrator_applied_migrations {
+        let app_name = (migration.app().to_string(), migration.name().to_string());
+        // Migration present in full migration list is added so its checksum is
+        // stored instead of checksum of old migrator migration
+        let Some(full_migration) = full_migration_list
+            .iter()
+            .find(|full_migration| full_migration == &&migration)
+        else {
+            report.unknown.push(app_name);
+            continue;
+        };
+        if already_applied_migration
+            .iter()
+            .any(|applied| applied == &migration)
+        {
+            report.already_applied.push(app_name);
+            continue;
+        }
+        if !dry_run {
+            match applied_time {
+                Some(applied_time) => {
+                    migrator
+                        .add_migration_to_db_table_with_applied_time(
+                            connection,
+                            full_migration,
+                            &applied_time,
+                        )
+                        .await?;
+                }
+                None => {
+                    migrator
+                        .add_migration_to_db_table(connection, full_migration)
+                        .await?;
+                }
+            }
+            migrator
+                .add_history_to_db_table(connection, full_migration, HistoryEvent::Sync)
+                .await?;
+        }
+        report.inserted.push(app_name);
+    }
+    Ok(report)
+}
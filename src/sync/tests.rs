@@ -0,0 +1,111 @@
+use super::{civil_from_days, days_from_civil, parse_rfc3339, rfc3339_to_utc};
+
+#[test]
+fn civil_days_round_trip() {
+    assert_eq!(days_from_civil(1970, 1, 1), 0);
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    // every day of four centuries which include leap and non leap century
+    // years
+    for days in days_from_civil(1800, 1, 1)..days_from_civil(2200, 1, 1) {
+        let (year, month, day) = civil_from_days(days);
+        assert_eq!(days_from_civil(year, month, day), days);
+    }
+    assert_eq!(
+        days_from_civil(2000, 3, 1) - days_from_civil(2000, 2, 28),
+        2
+    );
+    assert_eq!(
+        days_from_civil(1900, 3, 1) - days_from_civil(1900, 2, 28),
+        1
+    );
+    assert_eq!(
+        days_from_civil(2024, 3, 1) - days_from_civil(2024, 2, 28),
+        2
+    );
+    assert_eq!(
+        days_from_civil(2023, 3, 1) - days_from_civil(2023, 2, 28),
+        1
+    );
+}
+
+#[test]
+fn rfc3339_leap_years() {
+    assert_eq!(
+        rfc3339_to_utc("2024-02-29T12:00:00Z"),
+        "2024-02-29 12:00:00"
+    );
+    assert_eq!(
+        rfc3339_to_utc("2000-02-29T12:00:00Z"),
+        "2000-02-29 12:00:00"
+    );
+    // offset moves leap day to next day
+    assert_eq!(
+        rfc3339_to_utc("2024-02-29T23:30:00-01:00"),
+        "2024-03-01 00:30:00"
+    );
+    // 1900 and 2023 are not leap years
+    assert_eq!(parse_rfc3339("1900-02-29T00:00:00Z"), None);
+    assert_eq!(parse_rfc3339("2023-02-29T00:00:00Z"), None);
+}
+
+#[test]
+fn rfc3339_offsets() {
+    assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_rfc3339("1970-01-01T05:30:00+05:30"), Some(0));
+    assert_eq!(parse_rfc3339("1969-12-31T20:00:00-04:00"), Some(0));
+    assert_eq!(parse_rfc3339("1969-12-31T23:59:59Z"), Some(-1));
+    // offset moves timestamp to previous and next year
+    assert_eq!(
+        rfc3339_to_utc("2024-01-01T01:00:00+05:30"),
+        "2023-12-31 19:30:00"
+    );
+    assert_eq!(
+        rfc3339_to_utc("2023-12-31T23:00:00-02:00"),
+        "2024-01-01 01:00:00"
+    );
+    // lower case separator and utc designator and space separator
+    assert_eq!(
+        rfc3339_to_utc("2024-01-02t03:04:05z"),
+        "2024-01-02 03:04:05"
+    );
+    assert_eq!(
+        rfc3339_to_utc("2024-01-02 03:04:05+00:00"),
+        "2024-01-02 03:04:05"
+    );
+}
+
+#[test]
+fn rfc3339_fractional_seconds() {
+    assert_eq!(
+        rfc3339_to_utc("2024-01-02T03:04:05.123Z"),
+        "2024-01-02 03:04:05"
+    );
+    assert_eq!(
+        rfc3339_to_utc("2024-01-02T03:04:05.999999999+05:30"),
+        "2024-01-01 21:34:05"
+    );
+    assert_eq!(parse_rfc3339("2024-01-02T03:04:05.Z"), None);
+}
+
+#[test]
+fn rfc3339_invalid() {
+    for timestamp in [
+        "",
+        "2024-01-02",
+        "2024-01-02 03:04:05",
+        "2024-13-02T03:04:05Z",
+        "2024-04-31T03:04:05Z",
+        "2024-01-02T24:00:00Z",
+        "2024-01-02T03:60:00Z",
+        "2024-01-02T03:04:60Z",
+        "2024-01-02T03:04:05+24:00",
+        "2024-01-02T03:04:05+0530",
+        "2024-01-02T03:04:05..1Z",
+        "2024/01/02T03:04:05Z",
+    ] {
+        assert_eq!(parse_rfc3339(timestamp), None, "{timestamp}");
+        // timestamp which cannot be parsed is returned as it is
+        assert_eq!(rfc3339_to_utc(timestamp), timestamp);
+    }
+}
@@ -0,0 +1,195 @@
+//! Module for waiting without depending upon async runtime
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::future::poll_fn;
+use std::pin::{Pin, pin};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+
+/// Sleeps which are waited by timer thread
+#[derive(Default)]
+struct TimerState {
+    /// Deadline of registered sleeps ordered by earliest deadline
+    deadlines: BinaryHeap<Reverse<(Instant, u64)>>,
+    /// Waker of registered sleeps which are not yet completed
+    wakers: HashMap<u64, Waker>,
+    next_id: u64,
+}
+
+/// Timer shared by all sleeps. Single thread waits for earliest deadline and
+/// wakes sleeps whose deadline is elapsed
+struct Timer {
+    state: Mutex<TimerState>,
+    condvar: Condvar,
+}
+
+impl Timer {
+    /// Get shared timer. Timer thread is started when timer is used for first
+    /// time
+    ///
+    /// # Errors
+    /// If timer thread cannot be spawned
+    fn get() -> Result<&'static Self, Error> {
+        static TIMER: OnceLock<Result<Timer, (std::io::ErrorKind, String)>> = OnceLock::new();
+        TIMER
+            .get_or_init(|| {
+                // timer thread waits till timer is initialized before running
+                thread::Builder::new()
+                    .name("sqlx_migrator-timer".to_string())
+                    .spawn(|| {
+                        if let Ok(timer) = Self::get() {
+                            timer.run();
+                        }
+                    })
+                    .map_err(|error| {
+                        tracing::error!("failed to spawn timer thread: {error}");
+                        (
+                            error.kind(),
+                            format!("failed to spawn timer thread: {error}"),
+                        )
+                    })?;
+                Ok(Self {
+                    state: Mutex::new(TimerState::default()),
+                    condvar: Condvar::new(),
+                })
+            })
+            .as_ref()
+            .map_err(|(kind, message)| Error::StdIo(std::io::Error::new(*kind, message.clone())))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TimerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wake sleeps whose deadline is elapsed and wait till next deadline
+    fn run(&self) -> ! {
+        let mut state = self.lock();
+        loop {
+            let now = Instant::now();
+            while let Some(&Reverse((deadline, id))) = state.deadlines.peek()
+                && deadline <= now
+            {
+                state.deadlines.pop();
+                if let Some(waker) = state.wakers.remove(&id) {
+                    waker.wake();
+                }
+            }
+            state = match state.deadlines.peek() {
+                Some(&Reverse((deadline, _))) => {
+                    self.condvar
+                        .wait_timeout(state, deadline.saturating_duration_since(now))
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => {
+                    self.condvar
+                        .wait(state)
+                        .unwrap_or_else(PoisonError::into_inner)
+                }
+            };
+        }
+    }
+}
+
+/// Future which completes after duration is elapsed
+///
+/// Deadline is waited by single timer thread shared by all sleeps so sleep
+/// works with any async runtime used by sqlx. Sleep is removed from timer
+/// when it is dropped
+pub(crate) struct Sleep {
+    timer: &'static Timer,
+    deadline: Option<Instant>,
+    id: Option<u64>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // deadline which cannot be represented is never elapsed
+        let Some(deadline) = self.deadline else {
+            return Poll::Pending;
+        };
+        if deadline <= Instant::now() {
+            return Poll::Ready(());
+        }
+        let timer = self.timer;
+        let mut state = timer.lock();
+        if let Some(id) = self.id {
+            // sleep is removed from timer once its deadline is elapsed
+            let Some(waker) = state.wakers.get_mut(&id) else {
+                return Poll::Ready(());
+            };
+            waker.clone_from(cx.waker());
+            return Poll::Pending;
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        let earliest = state
+            .deadlines
+            .peek()
+            .is_none_or(|&Reverse((earliest, _))| deadline < earliest);
+        state.deadlines.push(Reverse((deadline, id)));
+        state.wakers.insert(id, cx.waker().clone());
+        self.id = Some(id);
+        // timer thread waits for earliest deadline so it needs to be notified
+        // about new earlier deadline
+        if earliest {
+            timer.condvar.notify_one();
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.timer.lock();
+            if state.wakers.remove(&id).is_some() {
+                state
+                    .deadlines
+                    .retain(|&Reverse((_, deadline_id))| deadline_id != id);
+            }
+        }
+    }
+}
+
+/// Wait till provided duration is elapsed
+///
+/// # Errors
+/// If timer thread cannot be spawned
+pub(crate) fn sleep(duration: Duration) -> Result<Sleep, Error> {
+    Ok(Sleep {
+        timer: Timer::get()?,
+        deadline: Instant::now().checked_add(duration),
+        id: None,
+    })
+}
+
+/// Wait for future till provided duration is elapsed. Returns `None` if
+/// future is not completed within duration in which case future is dropped
+///
+/// # Errors
+/// If timer thread cannot be spawned. Future is not polled in such case
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Result<Option<F::Output>, Error>
+where
+    F: Future,
+{
+    let mut future = pin!(future);
+    let mut sleep = sleep(duration)?;
+    Ok(poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        if Pin::new(&mut sleep).poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    })
+    .await)
+}